@@ -0,0 +1,657 @@
+//! The `Board` type: checked cells in z-order along with which of them were givens and the size
+//! of the board
+//!
+//! `Board` derefs to `&[u8]` so it can be handed to every function taking a board slice, along
+//! with `board.size()`.
+
+use crate::board_ctrl::{Size, UnitKind, format_board, get_givens};
+use crate::symbols::Symbols;
+use crate::validation::board_validation;
+use std::fmt;
+use std::ops::{Deref, Index};
+use std::str::FromStr;
+
+/// Why a board could not be built or changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardError {
+    /// Not the number of cells the size of board needs
+    Length { expected: usize, found: usize },
+    /// No supported size of board has this many cells
+    Cells(usize),
+    /// A value larger than the side of the board
+    Value { cell: usize, value: u8 },
+    /// A character that is neither a symbol, a blank nor a separator
+    Char(char),
+    /// Givens that don't match the cells, either the wrong count or marking an empty cell
+    Givens,
+    /// Givens can't be overwritten
+    Given(usize),
+    /// The board reads as a valid puzzle both row by row and box by box, see `Board::read`
+    Layout,
+    /// A cell index past the end of the board
+    Cell(usize),
+    /// The board file could not be read
+    Read(String),
+    /// The board file could not be written
+    Write(String),
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::Length { expected, found } => {
+                write!(f, "expected {} cells, found {}", expected, found)
+            }
+            BoardError::Cells(len) => write!(f, "no board size has {} cells", len),
+            BoardError::Value { cell, value } => {
+                write!(f, "cell {} holds {}, too large for the board", cell, value)
+            }
+            BoardError::Char(c) => write!(f, "unexpected character {:?}", c),
+            BoardError::Givens => write!(f, "givens must mark filled cells of the board"),
+            BoardError::Given(cell) => write!(f, "cell {} is a given", cell),
+            BoardError::Layout => write!(f, "board could be row by row or box by box"),
+            BoardError::Cell(cell) => write!(f, "cell {} is off the board", cell),
+            BoardError::Read(err) => write!(f, "could not read board: {}", err),
+            BoardError::Write(err) => write!(f, "could not write board: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+/// A sudoku board
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Board {
+    cells: Vec<u8>,
+    givens: Vec<bool>,
+    size: Size,
+}
+
+impl Board {
+    /// Build a board from its cells, every filled cell is a given
+    /// The size is worked out from the number of cells, see `Size::from_cells`
+    ///
+    /// Parameters:
+    /// - `cells: Vec<u8>` - values in z-order, 0 for empty
+    pub fn new(cells: Vec<u8>) -> Result<Board, BoardError> {
+        let size = Size::from_cells(cells.len()).ok_or(BoardError::Cells(cells.len()))?;
+        Board::with_size(cells, size)
+    }
+
+    /// Build a board of a given size from its cells, every filled cell is a given
+    ///
+    /// Parameters:
+    /// - `cells: Vec<u8>` - values in z-order, 0 for empty
+    /// - `size: Size` - size of the board
+    pub fn with_size(cells: Vec<u8>, size: Size) -> Result<Board, BoardError> {
+        let givens = get_givens(&cells);
+        Board::with_givens(cells, givens, size)
+    }
+
+    /// Build a board that is already partly solved, keeping track of the original givens
+    ///
+    /// Parameters:
+    /// - `cells: Vec<u8>` - values in z-order, 0 for empty
+    /// - `givens: Vec<bool>` - which cells were givens, see `get_givens`
+    /// - `size: Size` - size of the board
+    pub fn with_givens(cells: Vec<u8>, givens: Vec<bool>, size: Size) -> Result<Board, BoardError> {
+        if cells.len() != size.cells() {
+            return Err(BoardError::Length {
+                expected: size.cells(),
+                found: cells.len(),
+            });
+        }
+        let too_large = |&(_, &v): &(usize, &u8)| v as usize > size.side();
+        if let Some((cell, &value)) = cells.iter().enumerate().find(too_large) {
+            return Err(BoardError::Value { cell, value });
+        }
+        if givens.len() != cells.len() || givens.iter().zip(&cells).any(|(&g, &v)| g && v == 0) {
+            return Err(BoardError::Givens);
+        }
+        Ok(Board {
+            cells,
+            givens,
+            size,
+        })
+    }
+
+    /// A board with every cell empty
+    ///
+    /// Parameters:
+    /// - `size: Size` - size of the board
+    pub fn empty(size: Size) -> Board {
+        Board {
+            cells: vec![0; size.cells()],
+            givens: vec![false; size.cells()],
+            size,
+        }
+    }
+
+    /// Read a board of a given size with the usual symbols for the size, like `Board::read`
+    ///
+    /// Parameters:
+    /// - `text: &str` - the board, like a board file
+    /// - `size: Size` - size of the board
+    pub fn parse(text: &str, size: Size) -> Result<Board, BoardError> {
+        Board::parse_with(text, size, &Symbols::detect(&read_cells(text)?, size))
+    }
+
+    /// Read a board of a given size written with the given symbols, in whichever layout it is
+    /// written, like `Board::read`
+    ///
+    /// Parameters:
+    /// - `text: &str` - the board, like a board file
+    /// - `size: Size` - size of the board
+    /// - `symbols: &Symbols` - alphabet the values are written with
+    pub fn parse_with(text: &str, size: Size, symbols: &Symbols) -> Result<Board, BoardError> {
+        let board = Board::parse_layout(text, size, symbols, Layout::detect(text))?;
+        if !could_be_boxes(text) {
+            return Ok(board);
+        }
+        // A row per line may also be an older z-order file written a box per line
+        let zorder = Board::parse_layout(text, size, symbols, Layout::ZOrder)?;
+        match (
+            board_validation(&board, size),
+            board_validation(&zorder, size),
+        ) {
+            (false, true) => Ok(zorder),
+            (true, true) if board != zorder => Err(BoardError::Layout),
+            _ => Ok(board),
+        }
+    }
+
+    /// Read a board of a given size laid out either way, written with the given symbols
+    /// Row-major boards are turned into z-order
+    ///
+    /// Parameters:
+    /// - `text: &str` - the board
+    /// - `size: Size` - size of the board
+    /// - `symbols: &Symbols` - alphabet the values are written with
+    /// - `layout: Layout` - order the cells are written in, see `Layout::detect`
+    pub fn parse_layout(
+        text: &str,
+        size: Size,
+        symbols: &Symbols,
+        layout: Layout,
+    ) -> Result<Board, BoardError> {
+        let values = read_values(&read_cells(text)?, symbols)?;
+        if layout == Layout::RowMajor && values.len() == size.cells() {
+            let mut cells = vec![0; values.len()];
+            for (i, &value) in values.iter().enumerate() {
+                cells[size.cell_id(i / size.side(), i % size.side())] = value;
+            }
+            return Board::with_size(cells, size);
+        }
+        Board::with_size(values, size)
+    }
+
+    /// Read a board, the same as `FromStr`
+    /// The layout is worked out from the shape of the lines, see `Layout::detect`. A single line,
+    /// or N lines of N, is read row by row unless only the box by box reading is a valid board.
+    /// When both readings are valid and differ the board is `BoardError::Layout`, use
+    /// `Board::parse_layout` to say which it is.
+    /// The size is worked out from the number of cells and the values are read with the usual
+    /// symbols for that size, or hex for a 16x16 board using `0` as a value, see `Symbols::detect`
+    ///
+    /// Parameters:
+    /// - `text: &str` - the board, ex: the contents of a board file
+    pub fn read(text: &str) -> Result<Board, BoardError> {
+        let cells = read_cells(text)?;
+        let size = Size::from_cells(cells.len()).ok_or(BoardError::Cells(cells.len()))?;
+        Board::parse_with(text, size, &Symbols::detect(&cells, size))
+    }
+
+    /// Size of the board
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// The cells in z-order
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    /// Which cells were givens
+    pub fn givens(&self) -> &[bool] {
+        &self.givens
+    }
+
+    /// Check if a cell was a given
+    ///
+    /// Parameters:
+    /// - `cell_id: usize` - index of the cell
+    pub fn is_given(&self, cell_id: usize) -> bool {
+        self.givens[cell_id]
+    }
+
+    /// Values of a unit, left to right and top to bottom
+    fn unit_values(&self, kind: UnitKind, id: usize) -> Vec<u8> {
+        self.size
+            .geometry()
+            .unit(kind, id)
+            .iter()
+            .map(|&c| self.cells[c])
+            .collect()
+    }
+
+    /// Values of a box, like `get_board_box`
+    pub fn get_box(&self, box_id: usize) -> Vec<u8> {
+        self.unit_values(UnitKind::Box, box_id)
+    }
+
+    /// Values of a row, like `get_board_row`
+    pub fn get_row(&self, row_id: usize) -> Vec<u8> {
+        self.unit_values(UnitKind::Row, row_id)
+    }
+
+    /// Values of a col, like `get_board_col`
+    pub fn get_col(&self, col_id: usize) -> Vec<u8> {
+        self.unit_values(UnitKind::Col, col_id)
+    }
+
+    /// Fill in or clear a cell, givens can't be changed
+    ///
+    /// Parameters:
+    /// - `cell_id: usize` - index of the cell
+    /// - `value: u8` - value to write, 0 to clear
+    pub fn set(&mut self, cell_id: usize, value: u8) -> Result<(), BoardError> {
+        if cell_id >= self.cells.len() {
+            return Err(BoardError::Cell(cell_id));
+        }
+        if value as usize > self.size.side() {
+            return Err(BoardError::Value {
+                cell: cell_id,
+                value,
+            });
+        }
+        if self.givens[cell_id] {
+            return Err(BoardError::Given(cell_id));
+        }
+        self.cells[cell_id] = value;
+        Ok(())
+    }
+
+    /// Take the cells out of the board
+    pub fn into_cells(self) -> Vec<u8> {
+        self.cells
+    }
+}
+
+impl Deref for Board {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.cells
+    }
+}
+
+impl AsRef<[u8]> for Board {
+    fn as_ref(&self) -> &[u8] {
+        &self.cells
+    }
+}
+
+/// Index by cell idx, ex: `board[10]`
+impl Index<usize> for Board {
+    type Output = u8;
+
+    fn index(&self, cell: usize) -> &u8 {
+        &self.cells[cell]
+    }
+}
+
+/// Index by (row, col), ex: `board[(0, 4)]` is `r1c5`
+impl Index<(usize, usize)> for Board {
+    type Output = u8;
+
+    fn index(&self, (row_id, col_id): (usize, usize)) -> &u8 {
+        &self.cells[self.size.cell_id(row_id, col_id)]
+    }
+}
+
+/// Printed like `print_board`, which `FromStr` reads back
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_board(&self.cells, self.size))
+    }
+}
+
+/// Order the cells of a board are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Box by box, each box left to right then top to bottom, like `board.txt`
+    /// The same order as the cells of a `Board`
+    #[default]
+    ZOrder,
+    /// Row by row, each row left to right, the way most other tools and websites write puzzles
+    RowMajor,
+}
+
+impl Layout {
+    /// Work out the layout of a board from the shape of its lines
+    /// A grid drawn with `|` like `print_board`, a single line of cells, or as many lines as there
+    /// are cells on each (ex: 9 lines of 9), is row-major. Anything else, like the 27 lines of 3 of
+    /// a z-order board file, is z-order. Lines without cells, such as `------` rules, don't count
+    ///
+    /// Parameters:
+    /// - `text: &str` - the board
+    pub fn detect(text: &str) -> Layout {
+        if text.contains('|') || could_be_boxes(text) {
+            Layout::RowMajor
+        } else {
+            Layout::ZOrder
+        }
+    }
+}
+
+/// Check if a board not drawn as a grid is a single line, or N lines of N
+/// Row-major boards are written that way, but so is a z-order board with a box per line
+fn could_be_boxes(text: &str) -> bool {
+    if text.contains('|') {
+        return false;
+    }
+    let lines: Vec<usize> = text
+        .lines()
+        .map(|line| line.chars().filter(|&c| is_cell(c)).count())
+        .filter(|&cells| cells > 0)
+        .collect();
+    match lines.as_slice() {
+        [_] => true,
+        [first, ..] => lines.len() == *first && lines.iter().all(|n| n == first),
+        [] => false,
+    }
+}
+
+/// Check if a char is a cell of a board, any letter, digit, `.` or `_`
+fn is_cell(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '.' || c == '_'
+}
+
+/// Split a board into the chars of its cells, see `is_cell`
+/// Whitespace and other punctuation between them is ignored. In a grid drawn with `|`, like
+/// `print_board`, only the lines with a `|` hold cells, see `read_grid_line`
+fn read_cells(text: &str) -> Result<Vec<char>, BoardError> {
+    let mut cells = Vec::new();
+    if text.contains('|') {
+        for line in text.lines().filter(|line| line.contains('|')) {
+            read_grid_line(line, &mut cells)?;
+        }
+    } else {
+        push_cells(text, &mut cells)?;
+    }
+    Ok(cells)
+}
+
+/// Add the cells of one row of a drawn grid
+/// A line closed by a `|` starts with its row label, which is skipped. Between commas each cell
+/// takes a field of its own, so a field left blank, like `9,  , 2`, is an empty cell
+fn read_grid_line(line: &str, cells: &mut Vec<char>) -> Result<(), BoardError> {
+    let mut parts: Vec<&str> = line.split('|').collect();
+    if line.trim_end().ends_with('|') {
+        parts.remove(0);
+    }
+    for part in parts {
+        if !part.contains(',') {
+            push_cells(part, cells)?;
+            continue;
+        }
+        for field in part.split(',') {
+            match field.trim() {
+                "" => cells.push('.'),
+                field => push_cells(field, cells)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the cells of some text, see `read_cells`
+fn push_cells(text: &str, cells: &mut Vec<char>) -> Result<(), BoardError> {
+    for c in text.chars() {
+        if is_cell(c) {
+            cells.push(c);
+        } else if !c.is_whitespace() && !c.is_ascii_punctuation() {
+            return Err(BoardError::Char(c));
+        }
+    }
+    Ok(())
+}
+
+/// Turn the chars of the cells into values
+fn read_values(cells: &[char], symbols: &Symbols) -> Result<Vec<u8>, BoardError> {
+    cells
+        .iter()
+        .map(|&c| symbols.value(c).ok_or(BoardError::Char(c)))
+        .collect()
+}
+
+/// Parse a board like `Board::read`
+/// Each symbol or blank is a cell, whitespace and other punctuation between them is ignored.
+/// Either layout is read, see `Layout::detect`, and a grid drawn like `Display` output reads back
+/// in. The size is worked out from the number of cells and the values are read with the usual
+/// symbols for that size, see `Symbols::detect`. Use `Board::parse_layout` for other box shapes or
+/// symbols
+impl FromStr for Board {
+    type Err = BoardError;
+
+    fn from_str(s: &str) -> Result<Board, BoardError> {
+        Board::read(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_ctrl::format_board_file;
+
+    fn sample() -> Board {
+        include_str!("../board.txt").parse().unwrap()
+    }
+
+    /// The rows of a board, one line each, `.` for empty
+    fn rows(board: &Board, separator: &str) -> String {
+        let size = board.size();
+        let mut text = String::new();
+        for row_id in 0..size.side() {
+            let row: Vec<String> = board
+                .get_row(row_id)
+                .iter()
+                .map(|&v| match v {
+                    0 => ".".to_string(),
+                    _ => v.to_string(),
+                })
+                .collect();
+            text.push_str(&row.join(separator));
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn new_works_out_size() {
+        let board = Board::new(vec![0; 16]).unwrap();
+        assert_eq!(board.size(), Size::new(2, 2).unwrap());
+        assert_eq!(Board::new(vec![0; 81]).unwrap().size(), Size::CLASSIC);
+        assert_eq!(Board::new(vec![0; 80]), Err(BoardError::Cells(80)));
+    }
+
+    #[test]
+    fn new_checks_cells() {
+        let size = Size::new(2, 2).unwrap();
+        let found = Board::with_size(vec![0; 81], size);
+        assert_eq!(
+            found,
+            Err(BoardError::Length {
+                expected: 16,
+                found: 81
+            })
+        );
+        let mut cells = vec![0; 16];
+        cells[3] = 5;
+        assert_eq!(
+            Board::new(cells),
+            Err(BoardError::Value { cell: 3, value: 5 })
+        );
+        let mut givens = vec![false; 16];
+        givens[0] = true;
+        assert_eq!(
+            Board::with_givens(vec![0; 16], givens, size),
+            Err(BoardError::Givens)
+        );
+        assert_eq!(
+            Board::with_givens(vec![0; 16], vec![false; 15], size),
+            Err(BoardError::Givens)
+        );
+    }
+
+    #[test]
+    fn givens_are_filled_cells() {
+        let board = sample();
+        assert_eq!(board.givens().len(), 81);
+        for (cell, &value) in board.cells().iter().enumerate() {
+            assert_eq!(board.is_given(cell), value != 0);
+        }
+    }
+
+    #[test]
+    fn parse_board() {
+        let board = Board::parse(
+            "1 . | 3 4\n3 4 | 1 2\n. 1 | _ 3\n4 3 | 2 1\n",
+            Size::new(2, 2).unwrap(),
+        );
+        let board = board.unwrap();
+        assert_eq!(board[(0, 1)], 0);
+        assert_eq!(board[(2, 2)], 0);
+        assert_eq!(board[(3, 0)], 4);
+        assert_eq!(
+            "12x4 .... .... ....".parse::<Board>(),
+            Err(BoardError::Char('x'))
+        );
+        assert_eq!("12é4".parse::<Board>(), Err(BoardError::Char('é')));
+        assert_eq!("123".parse::<Board>(), Err(BoardError::Cells(3)));
+    }
+
+    #[test]
+    fn set_protects_givens() {
+        let mut board = sample();
+        let empty = board.cells().iter().position(|&v| v == 0).unwrap();
+        let given = board.cells().iter().position(|&v| v != 0).unwrap();
+        assert_eq!(board.set(empty, 3), Ok(()));
+        assert_eq!(board[empty], 3);
+        assert_eq!(board.set(empty, 0), Ok(()));
+        assert_eq!(board[empty], 0);
+        assert_eq!(board.set(given, 1), Err(BoardError::Given(given)));
+        assert_eq!(
+            board.set(empty, 10),
+            Err(BoardError::Value {
+                cell: empty,
+                value: 10
+            })
+        );
+        assert_eq!(board.set(81, 1), Err(BoardError::Cell(81)));
+        assert_eq!(board, sample());
+    }
+
+    #[test]
+    fn detect_box_rows() {
+        let text = include_str!("../board.txt");
+        assert_eq!(Layout::detect(text), Layout::ZOrder);
+        assert_eq!(Board::read(text).unwrap(), sample());
+    }
+
+    #[test]
+    fn nine_lines_of_nine() {
+        let board = sample();
+        let text = rows(&board, "");
+        assert_eq!(Layout::detect(&text), Layout::RowMajor);
+        assert_eq!(Board::read(&text).unwrap(), board);
+        let spaced = rows(&board, " ");
+        assert_eq!(Board::read(&spaced).unwrap(), board);
+    }
+
+    #[test]
+    fn single_line_is_row_major() {
+        let text: String = rows(&sample(), "").split_whitespace().collect();
+        assert_eq!(Layout::detect(&text), Layout::RowMajor);
+        assert_eq!(text.parse::<Board>().unwrap(), sample());
+        let hard =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let board: Board = hard.parse().unwrap();
+        assert_eq!(board.get_box(0), vec![8, 0, 0, 0, 0, 3, 0, 7, 0]);
+    }
+
+    #[test]
+    fn box_per_line_falls_back_to_zorder() {
+        // Read row by row, board.txt repeats values in a col
+        let text: String = include_str!("../board.txt").split_whitespace().collect();
+        assert_eq!(Layout::detect(&text), Layout::RowMajor);
+        assert_eq!(text.parse::<Board>().unwrap(), sample());
+        let boxes = format_board_file(&sample(), Size::CLASSIC, &Symbols::digits());
+        let lines: Vec<String> = boxes
+            .lines()
+            .collect::<Vec<&str>>()
+            .chunks(3)
+            .map(|rows| rows.join(" "))
+            .collect();
+        assert_eq!(Board::read(&lines.join("\n")).unwrap(), sample());
+    }
+
+    #[test]
+    fn both_layouts_valid_is_ambiguous() {
+        // Both readings of a single given are valid, but put it in different cells
+        let text = format!("{}1{}", ".".repeat(3), ".".repeat(77));
+        assert_eq!(text.parse::<Board>(), Err(BoardError::Layout));
+        let symbols = Symbols::digits();
+        let board = Board::parse_layout(&text, Size::CLASSIC, &symbols, Layout::RowMajor);
+        assert_eq!(board.unwrap()[(0, 3)], 1);
+        // Readings that agree are fine
+        assert_eq!(
+            format!("1{}", ".".repeat(80)).parse::<Board>().unwrap()[0],
+            1
+        );
+    }
+
+    #[test]
+    fn drawn_grid_is_row_major() {
+        let board = sample();
+        let mut text = String::new();
+        for (row_id, line) in rows(&board, " ").lines().enumerate() {
+            if row_id > 0 && row_id % 3 == 0 {
+                text.push_str("------+-------+------\n");
+            }
+            let cells: Vec<&str> = line.split(' ').collect();
+            let boxes: Vec<String> = cells.chunks(3).map(|c| c.join(" ")).collect();
+            text.push_str(&boxes.join(" | "));
+            text.push('\n');
+        }
+        assert_eq!(Layout::detect(&text), Layout::RowMajor);
+        assert_eq!(Board::read(&text).unwrap(), board);
+    }
+
+    #[test]
+    fn sixteen_reads_hex_or_one_to_g() {
+        // A drawn grid so that only the row by row reading applies
+        let grid = |first: &str, blank: &str| {
+            let rows: Vec<String> = (0..16)
+                .map(|row_id| match row_id {
+                    0 => first.to_string(),
+                    _ => vec![blank.repeat(4); 4].join("|"),
+                })
+                .collect();
+            rows.join("\n")
+        };
+        let hex = Board::read(&grid("0123|4567|89AB|CDEF", ".")).unwrap();
+        assert_eq!(hex.get_row(0), (1..=16).collect::<Vec<u8>>());
+        let extended = Board::read(&grid("1234|5678|9ABC|DEFG", "0")).unwrap();
+        assert_eq!(extended, hex);
+    }
+
+    #[test]
+    fn display_reads_back() {
+        let board = sample();
+        assert_eq!(Layout::detect(&board.to_string()), Layout::RowMajor);
+        assert_eq!(board.to_string().parse::<Board>().unwrap(), board);
+        let small = Board::new(vec![1, 0, 3, 4, 0, 0, 1, 2, 0, 1, 0, 3, 0, 0, 2, 1]).unwrap();
+        assert_eq!(small.to_string().parse::<Board>().unwrap(), small);
+    }
+}
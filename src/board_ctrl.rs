@@ -1,24 +1,298 @@
+//! # Terminology
+//! - Box: the rectangle that makes subparts to the problem, 3x3 on a classic board
+//! - Row: A left to right reading of the board
+//! - Col: A top-down reading of the board
+//! - Cell: A given position in the board to fill
+//! - Side: the number of cells in each box|row|col, also the largest value
+//!
+//! Boards of any `Size` are stored box by box. The free functions taking `u8` cell ids describe
+//! the classic board the techniques use (see `Technique`), `Size` and `Geometry` every size.
+
+use crate::board::{Board, BoardError, Layout};
+use crate::symbols::Symbols;
+use std::fmt;
 use std::fs;
+use std::sync::OnceLock;
 
-/// # Terminology
-/// - Box: the 3x3 square that makes subparts to the problem
-/// - Row: A left to right reading of the board
-/// - Col: A top-down reading of the board
-/// - Cell: A given position in the board to fill
-const TERMINOLOGY: () = ();
+/// Parse board input file and create board
+/// file can be in 'zorder' esque box orientation like `board.txt`, or row input (9 lines of 9, a
+/// single line of 81, or a grid drawn like `print_board`), see `Board::read`.
+/// The size of the board is worked out from the number of cells, see `Size::from_cells`, and its
+/// values are read with the usual symbols for that size, see `Symbols::detect`
+///
+/// Expects the parameters:
+/// - `filename: &str` - path to input file
+pub fn read_board_file(filename: &str) -> Result<Board, BoardError> {
+    let text = fs::read_to_string(filename).map_err(|err| BoardError::Read(err.to_string()))?;
+    Board::read(&text)
+}
 
-/// Parse board input file and create board vector
-/// file should be in 'zorder' esque box orientation for input, not row input.
+/// Parse a board input file of a given size and layout, written with the given symbols
 ///
 /// Expects the parameters:
 /// - `filename: &str` - path to input file
-pub fn read_board_file(filename: &str) -> Vec<u8> {
-    fs::read_to_string(filename)
-        .expect("Something went wrong reading the file")
-        .chars()
-        .filter_map(|c| c.to_digit(10))
-        .map(|c| c as u8)
-        .collect()
+/// - `size: Size` - size of the board
+/// - `symbols: &Symbols` - alphabet the values are written with
+/// - `layout: Layout` - order the cells are written in
+pub fn read_board_file_with(
+    filename: &str,
+    size: Size,
+    symbols: &Symbols,
+    layout: Layout,
+) -> Result<Board, BoardError> {
+    let text = fs::read_to_string(filename).map_err(|err| BoardError::Read(err.to_string()))?;
+    Board::parse_layout(&text, size, symbols, layout)
+}
+
+/// Mark the given cells (clues) of a board
+/// Call on the board straight from `read_board_file`, before anything is written in,
+/// so that solved cells can later be told apart from clues
+///
+/// Expects the parameters:
+/// - `board: &[u8]` - representing board in 1D array
+pub fn get_givens(board: &[u8]) -> Vec<bool> {
+    board.iter().map(|&cell| cell != 0).collect()
+}
+
+/// Largest side of a supported board, pencil marks hold at most this many values
+pub const MAX_SIDE: usize = 25;
+
+/// Size of a board, given by the shape of its boxes
+/// A board with boxes `box_width` cols wide and `box_height` rows tall has `box_width * box_height`
+/// rows, cols and boxes, and values from 1 up to that side. There are `box_height` boxes across
+/// the board and `box_width` boxes down it
+///
+/// Boxes are numbered left to right, then top to bottom, and cells within a box the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Size {
+    box_width: usize,
+    box_height: usize,
+}
+
+impl Size {
+    /// The classic 9x9 board with 3x3 boxes
+    pub const CLASSIC: Size = Size {
+        box_width: 3,
+        box_height: 3,
+    };
+
+    /// Size of a board with the given box shape, `None` if the side is 0 or above `MAX_SIDE`
+    ///
+    /// Expects parameters:
+    /// - `box_width: usize` - cols in each box
+    /// - `box_height: usize` - rows in each box
+    pub const fn new(box_width: usize, box_height: usize) -> Option<Size> {
+        if box_width == 0 || box_height == 0 || box_width * box_height > MAX_SIDE {
+            None
+        } else {
+            Some(Size {
+                box_width,
+                box_height,
+            })
+        }
+    }
+
+    /// The usual size for a board of `cells` cells, `None` if no size has that many cells
+    /// Boxes are as close to square as possible and wider than tall, ex: 6x6 has 3x2 boxes.
+    /// A prime side, ex: 7x7, only has boxes a single row tall, which are no boxes at all, so it is
+    /// `None` too. Use `Size::new` to build one anyway
+    ///
+    /// Expects parameters:
+    /// - `cells: usize` - cells on the board
+    pub fn from_cells(cells: usize) -> Option<Size> {
+        let side = (1..=MAX_SIDE).find(|side| side * side == cells)?;
+        let box_height = (1..=side)
+            .filter(|&h| h > 1 && side % h == 0 && h * h <= side)
+            .max()?;
+        Size::new(side / box_height, box_height)
+    }
+
+    /// Cols in each box
+    pub const fn box_width(self) -> usize {
+        self.box_width
+    }
+
+    /// Rows in each box
+    pub const fn box_height(self) -> usize {
+        self.box_height
+    }
+
+    /// Cells in each box|row|col, also the number of each and the largest value
+    pub const fn side(self) -> usize {
+        self.box_width * self.box_height
+    }
+
+    /// Cells on the board
+    pub const fn cells(self) -> usize {
+        self.side() * self.side()
+    }
+
+    /// Find box ID for given cell ID
+    ///
+    /// Expects parameters:
+    /// - `cell_id: usize` - index of given cell
+    pub const fn box_id(self, cell_id: usize) -> usize {
+        cell_id / self.side()
+    }
+
+    /// Find the board row pertaining to a given cell
+    ///
+    /// Expects parameters:
+    /// - `cell_id: usize` - index of given cell
+    pub const fn cell_row(self, cell_id: usize) -> usize {
+        let box_row = self.box_id(cell_id) / self.box_height;
+        let inner_box_row = cell_id % self.side() / self.box_width;
+        inner_box_row + box_row * self.box_height
+    }
+
+    /// Find the board col pertaining to a given cell
+    ///
+    /// Expects parameters:
+    /// - `cell_id: usize` - index of given cell
+    pub const fn cell_col(self, cell_id: usize) -> usize {
+        let box_col = self.box_id(cell_id) % self.box_height;
+        let inner_box_col = cell_id % self.box_width;
+        inner_box_col + box_col * self.box_width
+    }
+
+    /// Find the cell idx at a given board row and col, the inverse of `cell_row` and `cell_col`
+    ///
+    /// Expects parameters:
+    /// - `row_id: usize` - index of given row (Y axis coordinate)
+    /// - `col_id: usize` - index of given col (X axis coordinate)
+    pub const fn cell_id(self, row_id: usize, col_id: usize) -> usize {
+        let box_id = (row_id / self.box_height) * self.box_height + col_id / self.box_width;
+        box_id * self.side() + (row_id % self.box_height) * self.box_width + col_id % self.box_width
+    }
+
+    /// Check if two different cells share a box|row|col
+    ///
+    /// Expects parameters:
+    /// - `cell_a: usize` - index of first cell
+    /// - `cell_b: usize` - index of second cell
+    pub const fn cells_see(self, cell_a: usize, cell_b: usize) -> bool {
+        cell_a != cell_b
+            && (self.box_id(cell_a) == self.box_id(cell_b)
+                || self.cell_row(cell_a) == self.cell_row(cell_b)
+                || self.cell_col(cell_a) == self.cell_col(cell_b))
+    }
+
+    /// Name a cell in row/col notation counting from 1, ex: cell 0 is `r1c1`
+    ///
+    /// Expects parameters:
+    /// - `cell_id: usize` - index of given cell
+    pub fn cell_name(self, cell_id: usize) -> String {
+        format!(
+            "r{}c{}",
+            self.cell_row(cell_id) + 1,
+            self.cell_col(cell_id) + 1
+        )
+    }
+
+    /// Unit and peer tables for boards of this size, built the first time they are asked for
+    pub fn geometry(self) -> &'static Geometry {
+        // One slot for every box shape `Size::new` accepts
+        static GEOMETRIES: [OnceLock<Geometry>; MAX_SIDE * MAX_SIDE] =
+            [const { OnceLock::new() }; MAX_SIDE * MAX_SIDE];
+        let slot = (self.box_width - 1) * MAX_SIDE + (self.box_height - 1);
+        GEOMETRIES[slot].get_or_init(|| Geometry::new(self))
+    }
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Size::CLASSIC
+    }
+}
+
+/// Written as cols x rows of the whole board, then of a box, ex: `6x6 (3x2 boxes)`
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} ({}x{} boxes)",
+            self.side(),
+            self.side(),
+            self.box_width,
+            self.box_height
+        )
+    }
+}
+
+/// Cell indices of every unit and the peers of every cell, for one size of board
+/// The techniques read the classic board's tables through `get_all_units` and `get_peer_cells`,
+/// see `Size::geometry`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Geometry {
+    size: Size,
+    /// Boxes, then rows, then cols
+    units: Vec<Vec<usize>>,
+    /// Peers of each cell in index order
+    peers: Vec<Vec<usize>>,
+}
+
+impl Geometry {
+    fn new(size: Size) -> Geometry {
+        let side = size.side();
+        let mut units = Vec::with_capacity(3 * side);
+        units.extend((0..side).map(|id| (id * side..(id + 1) * side).collect()));
+        units.extend((0..side).map(|id| (0..side).map(|k| size.cell_id(id, k)).collect()));
+        units.extend((0..side).map(|id| (0..side).map(|k| size.cell_id(k, id)).collect()));
+        let peers = (0..size.cells())
+            .map(|cell| {
+                (0..size.cells())
+                    .filter(|&other| size.cells_see(cell, other))
+                    .collect()
+            })
+            .collect();
+        Geometry { size, units, peers }
+    }
+
+    /// Size of board the tables are for
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Cell indices of every unit: boxes, then rows, then cols
+    /// Rows read left to right and cols top to bottom
+    pub fn units(&self) -> &[Vec<usize>] {
+        &self.units
+    }
+
+    /// Cell indices of a single box|row|col
+    ///
+    /// Expects the parameters:
+    /// - `kind: UnitKind` - box, row or col
+    /// - `id: usize` - the ID of the box|row|col
+    pub fn unit(&self, kind: UnitKind, id: usize) -> &[usize] {
+        let offset = match kind {
+            UnitKind::Box => 0,
+            UnitKind::Row => 1,
+            UnitKind::Col => 2,
+        };
+        &self.units[offset * self.size.side() + id]
+    }
+
+    /// Positions in `units` of the box, row and col of a cell
+    ///
+    /// Expects parameters:
+    /// - `cell_id: usize` - index of given cell
+    pub fn cell_units(&self, cell_id: usize) -> [usize; 3] {
+        let side = self.size.side();
+        [
+            self.size.box_id(cell_id),
+            side + self.size.cell_row(cell_id),
+            2 * side + self.size.cell_col(cell_id),
+        ]
+    }
+
+    /// The cells sharing a box|row|col with a cell (its peers), in index order
+    ///
+    /// Expects parameters:
+    /// - `cell_id: usize` - index of given cell
+    pub fn peers(&self, cell_id: usize) -> &[usize] {
+        &self.peers[cell_id]
+    }
 }
 
 /// Find box ID for given cell ID
@@ -31,8 +305,8 @@ pub fn read_board_file(filename: &str) -> Vec<u8> {
 /// | 3 | 4 | 5 |
 ///
 /// | 6 | 7 | 8 |
-pub fn get_box_id(cell_id: u8) -> u8 {
-    cell_id / 9 // floored due to integer division
+pub const fn get_box_id(cell_id: u8) -> u8 {
+    Size::CLASSIC.box_id(cell_id as usize) as u8
 }
 
 /// Find box row on board for a given box_id
@@ -45,7 +319,7 @@ pub fn get_box_id(cell_id: u8) -> u8 {
 /// | 1 | 1 | 1 |
 ///
 /// | 2 | 2 | 2 |
-pub fn get_box_row(box_id: u8) -> u8 {
+pub const fn get_box_row(box_id: u8) -> u8 {
     box_id / 3 // floored due to integer division
 }
 
@@ -59,7 +333,7 @@ pub fn get_box_row(box_id: u8) -> u8 {
 /// | 0 | 1 | 2 |
 ///
 /// | 0 | 1 | 2 |
-pub fn get_box_col(box_id: u8) -> u8 {
+pub const fn get_box_col(box_id: u8) -> u8 {
     box_id % 3
 }
 
@@ -67,22 +341,25 @@ pub fn get_box_col(box_id: u8) -> u8 {
 ///
 /// Expects parameters:
 /// - `cell_id: u8`  - index of given cell
-pub fn get_cell_row(cell_id: u8) -> u8 {
-    let box_row = get_box_row(get_box_id(cell_id));
-    let inner_box_row = cell_id % 9 / 3;
-    let board_row = inner_box_row + box_row * 3;
-    board_row
+pub const fn get_cell_row(cell_id: u8) -> u8 {
+    Size::CLASSIC.cell_row(cell_id as usize) as u8
 }
 
 /// Find the board col pertaining to a given cell
 ///
 /// Expects parameters:
 /// - `cell_id: u8`  - index of given cell
-pub fn get_cell_col(cell_id: u8) -> u8 {
-    let box_col = get_box_col(get_box_id(cell_id));
-    let inner_box_col = cell_id % 3;
-    let board_col = inner_box_col + box_col * 3;
-    board_col
+pub const fn get_cell_col(cell_id: u8) -> u8 {
+    Size::CLASSIC.cell_col(cell_id as usize) as u8
+}
+
+/// Find the cell idx at a given board row and col, the inverse of `get_cell_row` and `get_cell_col`
+///
+/// Expects parameters:
+/// - `row_id: u8`  - index of given row (Y axis coordinate)
+/// - `col_id: u8`  - index of given col (X axis coordinate)
+pub const fn get_cell_id(row_id: u8, col_id: u8) -> u8 {
+    Size::CLASSIC.cell_id(row_id as usize, col_id as usize) as u8
 }
 
 /// Find row starting idx given any cell idx [0-81)
@@ -94,8 +371,7 @@ pub fn row_start_from_cid(cell_id: u8) -> u8 {
     let row_in_box = pos_in_box / 3; // Div 3 to floor to nearest multiple of 3
     let idx_row_box = row_in_box * 3; // Mul 3 to find first idx of row in box
     let box_row = get_box_row(get_box_id(cell_id)); // Find the box row that this cell is in
-    let start_row_index = (box_row * 27) + idx_row_box; // Locate first idx position of row
-    start_row_index
+    (box_row * 27) + idx_row_box // Locate first idx position of row
 }
 
 /// Find the row starting idx given a requested row id [0-9)
@@ -120,8 +396,7 @@ pub fn col_start_from_cid(cell_id: u8) -> u8 {
     let pos_in_box = cell_id % 9; // Mod 9 to identify cell position within box
     let col_in_box = pos_in_box % 3; // Mod 3 to identify the column within box
     let box_col = get_box_col(get_box_id(cell_id)); // Identify column of box
-    let start_col_index = (box_col * 9) + col_in_box; // Find top cell index of column
-    start_col_index
+    (box_col * 9) + col_in_box // Find top cell index of column
 }
 
 /// Find the col starting idx given a requested col idx [0-9)
@@ -138,15 +413,37 @@ pub fn col_start_from_idx(col_id: u8) -> u8 {
     }
 }
 
+/// Cell indices of all 27 units of a classic board: boxes 0-8, then rows 9-17, then cols 18-26
+/// Taken from `Size::CLASSIC.geometry()` as `u8` for the techniques, built the first time they are asked for
+fn classic_cells() -> &'static [[u8; 9]; 27] {
+    static CELLS: OnceLock<[[u8; 9]; 27]> = OnceLock::new();
+    CELLS.get_or_init(|| {
+        let units = Size::CLASSIC.geometry().units();
+        std::array::from_fn(|unit| std::array::from_fn(|k| units[unit][k] as u8))
+    })
+}
+
+/// The 20 peers of every cell of a classic board, see `get_peer_cells`
+fn classic_peers() -> &'static [[u8; 20]; 81] {
+    static PEERS: OnceLock<[[u8; 20]; 81]> = OnceLock::new();
+    PEERS.get_or_init(|| {
+        let geometry = Size::CLASSIC.geometry();
+        std::array::from_fn(|cell| std::array::from_fn(|k| geometry.peers(cell)[k] as u8))
+    })
+}
+
+/// Read the values of a unit's cells off the board
+fn unit_values(board: &[u8], cells: &[u8; 9]) -> [u8; 9] {
+    cells.map(|c| board[c as usize])
+}
+
 /// Fetch a single box from board for further processing
 ///
 /// expects the parameters:
 /// - `board: &[u8]` - representing board in 1D array
 /// - `box_id: u8` - the box ID from the board
-pub fn get_board_box(board: &[u8], box_id: u8) -> Vec<u8> {
-    let start_idx = (box_id * 9) as usize;
-    let box_to_check = board[start_idx..start_idx + 9].to_vec();
-    box_to_check
+pub fn get_board_box(board: &[u8], box_id: u8) -> [u8; 9] {
+    unit_values(board, get_box_cells(box_id))
 }
 
 /// Fetch a single row from board for further processing
@@ -154,16 +451,8 @@ pub fn get_board_box(board: &[u8], box_id: u8) -> Vec<u8> {
 /// Expects the parameters:
 /// - `board: &[u8]`  - representing board in 1D array
 /// - `row_id: u8`  - representing the row ID requested (Y axis coordinate)
-pub fn get_board_row(board: &[u8], row_id: u8) -> Vec<u8> {
-    let mut row: Vec<u8> = Vec::new();
-    let rci: usize = row_start_from_idx(row_id) as usize; // Must be cast to usize for indexing
-    for b in 0..3 {
-        for c in 0..3 {
-            let cell = *board.get(rci + c + (b * 9)).unwrap();
-            row.push(cell);
-        }
-    }
-    row
+pub fn get_board_row(board: &[u8], row_id: u8) -> [u8; 9] {
+    unit_values(board, get_row_cells(row_id))
 }
 
 /// Fetch a single col from board for further processing
@@ -171,148 +460,419 @@ pub fn get_board_row(board: &[u8], row_id: u8) -> Vec<u8> {
 /// Expects the parameters:
 /// - `board: &[u8]`  - representing board in 1D array
 /// - `col_id: u8`  - representing the col ID requested (X axis coordinate)
-pub fn get_board_col(board: &[u8], col_id: u8) -> Vec<u8> {
-    let mut col: Vec<u8> = Vec::new();
-    let cci: usize = col_start_from_idx(col_id) as usize; // must be cast to usize for indexing
-    for b in 0..3 {
-        for c in 0..3 {
-            let cell = *board.get(cci + (c * 3) + (b * 27)).unwrap();
-            col.push(cell);
-        }
-    }
-    col
+pub fn get_board_col(board: &[u8], col_id: u8) -> [u8; 9] {
+    unit_values(board, get_col_cells(col_id))
 }
 
-/// Print row of board with coordinate marker
+/// Fetch the cell indices of a single box, in the same order as `get_board_box`
 ///
 /// Expects the parameters:
-/// - `board: &[u8]`  - representing board in 1D array
-/// - `row_id: u8`  - Representing the row ID requested
-pub fn print_one_row(board: &[u8], row_id: u8) {
-    println!("Y X  0, 1, 2,  3, 4, 5,  6, 7, 8");
-    print!("{}  ", row_id);
-    let row = get_board_row(board, row_id);
-    for i in 0..9 {
-        let cell = if *row.get(i).unwrap() == 0 {
-            ' '
-        } else {
-            char::from(*row.get(i).unwrap() + 0x30)
+/// - `box_id: u8` - the box ID from the board
+pub fn get_box_cells(box_id: u8) -> &'static [u8; 9] {
+    &classic_cells()[box_id as usize]
+}
+
+/// Fetch the cell indices of a single row, in the same order as `get_board_row`
+///
+/// Expects the parameters:
+/// - `row_id: u8`  - representing the row ID requested (Y axis coordinate)
+pub fn get_row_cells(row_id: u8) -> &'static [u8; 9] {
+    &classic_cells()[9 + row_id as usize]
+}
+
+/// Fetch the cell indices of a single col, in the same order as `get_board_col`
+///
+/// Expects the parameters:
+/// - `col_id: u8`  - representing the col ID requested (X axis coordinate)
+pub fn get_col_cells(col_id: u8) -> &'static [u8; 9] {
+    &classic_cells()[18 + col_id as usize]
+}
+
+/// The three kinds of unit a cell belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitKind {
+    Box,
+    Row,
+    Col,
+}
+
+/// A box|row|col of the board and the cell indices it covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    pub kind: UnitKind,
+    pub id: u8,
+    pub cells: &'static [u8; 9],
+}
+
+impl fmt::Display for Unit {
+    /// Units are named from 1 like cells, ex: `row 1`, `box 9`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            UnitKind::Box => "box",
+            UnitKind::Row => "row",
+            UnitKind::Col => "col",
         };
-        if i % 3 == 0 {
-            print!("| {}, ", cell);
-        } else if i % 3 == 1 {
-            print!("{}, ", cell);
-        } else if i % 3 == 2 {
-            print!("{} ", cell);
-        }
+        write!(f, "{} {}", kind, self.id + 1)
     }
-    print!("|\n");
 }
 
-/// Print a single column of board with coordinate marker
+/// Fetch a single unit with its cell indices
 ///
 /// Expects the parameters:
-/// - `board: &[u8]`  - representing board in 1D array
-/// - `col_id: u8`  - Representing the Idx of the column start (topmost position)
-// Print a column, given the top number starting the column reading down
-pub fn print_one_col(board: &[u8], col_id: u8) {
-    println!("X Y  0, 1, 2,  3, 4, 5,  6, 7, 8");
-    print!("{}  ", col_id);
-    let col = get_board_col(board, col_id);
-    for i in 0..9 {
-        let cell = if *col.get(i).unwrap() == 0 {
-            ' '
+/// - `kind: UnitKind` - box, row or col
+/// - `id: u8` - the ID of the box|row|col
+pub fn get_unit(kind: UnitKind, id: u8) -> &'static Unit {
+    let offset = match kind {
+        UnitKind::Box => 0,
+        UnitKind::Row => 9,
+        UnitKind::Col => 18,
+    };
+    &get_all_units()[offset + id as usize]
+}
+
+/// Fetch all 27 units of the board, boxes first, then rows, then cols
+pub fn get_all_units() -> &'static [Unit] {
+    static UNITS: OnceLock<Vec<Unit>> = OnceLock::new();
+    UNITS.get_or_init(|| {
+        let cells = classic_cells();
+        [UnitKind::Box, UnitKind::Row, UnitKind::Col]
+            .iter()
+            .enumerate()
+            .flat_map(|(offset, &kind)| {
+                (0..9u8).map(move |id| Unit {
+                    kind,
+                    id,
+                    cells: &cells[offset * 9 + id as usize],
+                })
+            })
+            .collect()
+    })
+}
+
+/// Check if two different cells share a box|row|col
+///
+/// Expects parameters:
+/// - `cell_a: u8`  - index of first cell
+/// - `cell_b: u8`  - index of second cell
+pub const fn cells_see(cell_a: u8, cell_b: u8) -> bool {
+    Size::CLASSIC.cells_see(cell_a as usize, cell_b as usize)
+}
+
+/// Fetch the 20 cells sharing a box|row|col with a cell (its peers), in index order
+///
+/// Expects parameters:
+/// - `cell_id: u8`  - index of given cell
+pub fn get_peer_cells(cell_id: u8) -> &'static [u8; 20] {
+    &classic_peers()[cell_id as usize]
+}
+
+/// Name a cell in row/col notation counting from 1, ex: cell 0 is `r1c1`
+///
+/// Expects parameters:
+/// - `cell_id: u8`  - index of given cell
+pub fn cell_name(cell_id: u8) -> String {
+    Size::CLASSIC.cell_name(cell_id as usize)
+}
+
+/// Format the values of a box|row|col in groups of one box width, ex: `| 1, 2, 3 | 4, 5, 6 | 7, 8, 9 |`
+/// Empty cells are left blank, values are written with the usual symbols for the size, see `Symbols::for_size`
+///
+/// Expects the parameters:
+/// - `values: &[u8]`  - values read off the board, see `get_board_row`
+/// - `size: Size`  - size of the board the values were read from
+pub fn format_unit(values: &[u8], size: Size) -> String {
+    format_unit_with(values, size, &Symbols::for_size(size))
+}
+
+/// Format the values of a box|row|col like `format_unit`, with the given symbols
+/// Values past the end of the alphabet are written as `?`
+///
+/// Expects the parameters:
+/// - `values: &[u8]`  - values read off the board, see `get_board_row`
+/// - `size: Size`  - size of the board the values were read from
+/// - `symbols: &Symbols`  - alphabet to write the values with
+pub fn format_unit_with(values: &[u8], size: Size, symbols: &Symbols) -> String {
+    let cells: Vec<String> = values
+        .iter()
+        .map(|&value| match value {
+            0 => String::new(),
+            _ => symbols.symbol(value).unwrap_or('?').to_string(),
+        })
+        .collect();
+    join_unit(&cells, size)
+}
+
+/// Lay out the cells of a box|row|col as `format_unit` does
+/// Cells are padded to the width of the largest col number so they line up under it
+fn join_unit(cells: &[String], size: Size) -> String {
+    let width = (size.side() - 1).to_string().len();
+    let mut line = String::new();
+    for (i, cell) in cells.iter().enumerate() {
+        if i % size.box_width() == 0 {
+            line.push_str("| ");
+        }
+        if i % size.box_width() == size.box_width() - 1 {
+            line.push_str(&format!("{:>width$} ", cell));
         } else {
-            char::from(*col.get(i).unwrap() + 0x30)
-        };
-        if i % 3 == 0 {
-            print!("| {}, ", cell);
-        } else if i % 3 == 1 {
-            print!("{}, ", cell);
-        } else if i % 3 == 2 {
-            print!("{} ", cell);
+            line.push_str(&format!("{:>width$}, ", cell));
         }
     }
-    print!("|\n");
+    line.push('|');
+    line
+}
+
+/// Values of a box|row|col of a board of any size, in reading order
+fn board_unit(board: &[u8], size: Size, kind: UnitKind, id: usize) -> Vec<u8> {
+    size.geometry()
+        .unit(kind, id)
+        .iter()
+        .map(|&c| board[c])
+        .collect()
+}
+
+/// Col numbers lined up with the values of a row, with the separators blanked out
+fn unit_header(size: Size) -> String {
+    let labels: Vec<String> = (0..size.side()).map(|id| id.to_string()).collect();
+    join_unit(&labels, size).replace(['|', ','], " ")
 }
 
 /// Print row of board with coordinate marker
 ///
 /// Expects the parameters:
 /// - `board: &[u8]`  - representing board in 1D array
-/// - `row_id: u8`  - Representing the Idx of the row start (leftmost position)
-pub fn print_row(board: &[u8], row_id: u8) {
-    let row = get_board_row(board, row_id);
-    for i in 0..9 {
-        let cell = if *row.get(i).unwrap() == 0 {
-            ' '
-        } else {
-            char::from(*row.get(i).unwrap() + 0x30)
-        };
-
-        if i % 3 == 0 {
-            print!("| {}, ", cell);
-        } else if i % 3 == 1 {
-            print!("{}, ", cell);
-        } else if i % 3 == 2 {
-            print!("{} ", cell);
-        }
-    }
-    print!("|\n");
+/// - `size: Size`  - size of the board
+/// - `row_id: usize`  - Representing the row ID requested
+pub fn print_one_row(board: &[u8], size: Size, row_id: usize) {
+    let width = (size.side() - 1).to_string().len();
+    println!("{:<width$} X{}", "Y", unit_header(size).trim_end());
+    println!(
+        "{:<width$}  {}",
+        row_id,
+        format_unit(&board_unit(board, size, UnitKind::Row, row_id), size)
+    );
 }
 
 /// Print a single column of board with coordinate marker
 ///
 /// Expects the parameters:
 /// - `board: &[u8]`  - representing board in 1D array
-/// - `colstart_cell_id: u8`  - Representing the Idx of the column start (topmost position)
-// Print a column, given the top number starting the column reading down
-pub fn print_col(board: &[u8], colstart_cell_id: u8) {
-    let col = get_board_col(board, colstart_cell_id);
-    for i in 0..9 {
-        let cell = if *col.get(i).unwrap() == 0 {
-            ' '
-        } else {
-            char::from(*col.get(i).unwrap() + 0x30)
-        };
-        if i % 3 == 0 {
-            print!("| {}, ", cell);
-        } else if i % 3 == 1 {
-            print!("{}, ", cell);
-        } else if i % 3 == 2 {
-            print!("{} ", cell);
+/// - `size: Size`  - size of the board
+/// - `col_id: usize`  - Representing the col ID requested
+pub fn print_one_col(board: &[u8], size: Size, col_id: usize) {
+    let width = (size.side() - 1).to_string().len();
+    println!("{:<width$} Y{}", "X", unit_header(size).trim_end());
+    println!(
+        "{:<width$}  {}",
+        col_id,
+        format_unit(&board_unit(board, size, UnitKind::Col, col_id), size)
+    );
+}
+
+/// Print row of board
+///
+/// Expects the parameters:
+/// - `board: &[u8]`  - representing board in 1D array
+/// - `size: Size`  - size of the board
+/// - `row_id: usize`  - Representing the row ID requested
+pub fn print_row(board: &[u8], size: Size, row_id: usize) {
+    println!(
+        "{}",
+        format_unit(&board_unit(board, size, UnitKind::Row, row_id), size)
+    );
+}
+
+/// Print a single column of board
+///
+/// Expects the parameters:
+/// - `board: &[u8]`  - representing board in 1D array
+/// - `size: Size`  - size of the board
+/// - `col_id: usize`  - Representing the col ID requested
+pub fn print_col(board: &[u8], size: Size, col_id: usize) {
+    println!(
+        "{}",
+        format_unit(&board_unit(board, size, UnitKind::Col, col_id), size)
+    );
+}
+
+/// Format the full Sudoku Board State as printed by `print_board`
+///
+/// Expects the parameters:
+/// - `board: &[u8]`  - representing board in 1D array
+/// - `size: Size`  - size of the board
+pub fn format_board(board: &[u8], size: Size) -> String {
+    format_board_with(board, size, &Symbols::for_size(size))
+}
+
+/// Format the full Sudoku Board State like `format_board`, with the given symbols
+///
+/// Expects the parameters:
+/// - `board: &[u8]`  - representing board in 1D array
+/// - `size: Size`  - size of the board
+/// - `symbols: &Symbols`  - alphabet to write the values with
+pub fn format_board_with(board: &[u8], size: Size, symbols: &Symbols) -> String {
+    let label_width = (size.side() - 1).to_string().len();
+    let header = unit_header(size);
+    let mut text = format!("{:>w$}{}\nY", "X", header.trim_end(), w = label_width + 1);
+    let rule = format!("{}{}\n", " ".repeat(label_width), "-".repeat(header.len()));
+    for row_id in 0..size.side() {
+        if row_id % size.box_height() == 0 {
+            text.push_str(&rule);
         }
+        let values = board_unit(board, size, UnitKind::Row, row_id);
+        let row = format_unit_with(&values, size, symbols);
+        text.push_str(&format!("{:<w$} {}\n", row_id, row, w = label_width));
     }
-    print!("|\n");
+    text.push(' ');
+    text.push_str(&rule);
+    text
 }
 
 /// Print the full Sudoku Board State
 ///
 /// Expects the parameters:
 /// - `board: &[u8]`  - representing board in 1D array
-pub fn print_board(board: &[u8]) {
-    println!(" X  0  1  2   3  4  5   6  7  8");
-    print!("Y");
-    for row_id in 0..9 {
-        if row_id % 3 == 0 {
-            println!(" -------------------------------");
-        }
-        print!("{} ", row_id);
-        print_row(board, row_id);
+/// - `size: Size`  - size of the board
+pub fn print_board(board: &[u8], size: Size) {
+    print!("{}", format_board(board, size));
+}
+
+/// Format a board the way board files are laid out, so `read_board_file` can read it back
+/// Box by box, one line per row of the box with its symbols separated by spaces, ex: `9 0 2`
+/// Empty cells are written as `Symbols::blank`
+///
+/// Expects the parameters:
+/// - `board: &[u8]`  - representing board in 1D array
+/// - `size: Size`  - size of the board
+/// - `symbols: &Symbols`  - alphabet to write the values with
+pub fn format_board_file(board: &[u8], size: Size, symbols: &Symbols) -> String {
+    let mut text = String::new();
+    for box_row in board.chunks(size.box_width()) {
+        let row: Vec<String> = box_row
+            .iter()
+            .map(|&value| match value {
+                0 => symbols.blank(),
+                _ => symbols.symbol(value).unwrap_or('?'),
+            })
+            .map(String::from)
+            .collect();
+        text.push_str(&row.join(" "));
+        text.push('\n');
     }
-    println!("  -------------------------------");
+    text
+}
+
+/// Write a board file, see `format_board_file`
+///
+/// Expects the parameters:
+/// - `filename: &str` - path to output file
+/// - `board: &Board` - the board to write
+/// - `symbols: &Symbols`  - alphabet to write the values with
+pub fn write_board_file(
+    filename: &str,
+    board: &Board,
+    symbols: &Symbols,
+) -> Result<(), BoardError> {
+    fs::write(filename, format_board_file(board, board.size(), symbols))
+        .map_err(|err| BoardError::Write(err.to_string()))
 }
 
 /// Fill in a cell of the board
 ///
 /// Expects the parameters:
 /// - `board: &[u8]`  - representing board in 1D array
-/// - `cell_idx: u8`  - which cell in the board to fill
+/// - `cell_idx: usize`  - which cell in the board to fill
 /// - `value: u8`     - What value to write in the cell
-pub fn write_cell(board: &[u8], cell_idx: u8, value: u8) -> Vec<u8> {
+pub fn write_cell(board: &[u8], cell_idx: usize, value: u8) -> Vec<u8> {
     let mut new_board: Vec<u8> = board.to_vec();
-    new_board[cell_idx as usize] = value;
+    new_board[cell_idx] = value;
     new_board
 }
 
 //TODO Add some board generation capabilities instead of just reading from file for known good
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_cells_picks_box_shape() {
+        assert_eq!(Size::from_cells(81), Some(Size::CLASSIC));
+        assert_eq!(Size::from_cells(16), Size::new(2, 2));
+        assert_eq!(Size::from_cells(36), Size::new(3, 2));
+        assert_eq!(Size::from_cells(144), Size::new(4, 3));
+        assert_eq!(Size::from_cells(625), Size::new(5, 5));
+        assert_eq!(Size::from_cells(80), None);
+    }
+
+    #[test]
+    fn from_cells_rejects_prime_sides() {
+        for side in [1, 2, 3, 5, 7, 11, 13, 17, 19, 23] {
+            assert_eq!(Size::from_cells(side * side), None, "side {}", side);
+        }
+        // Still there for anyone who asks for a single row of boxes
+        assert_eq!(Size::new(7, 1).unwrap().side(), 7);
+    }
+
+    #[test]
+    fn geometry_is_built_once_per_size() {
+        let wide = Size::new(3, 2).unwrap();
+        let tall = Size::new(2, 3).unwrap();
+        assert!(std::ptr::eq(wide.geometry(), wide.geometry()));
+        assert!(!std::ptr::eq(wide.geometry(), tall.geometry()));
+        assert_eq!(wide.geometry().size(), wide);
+        assert_eq!(tall.geometry().size(), tall);
+        let largest = Size::new(MAX_SIDE, 1).unwrap();
+        assert_eq!(largest.geometry().units().len(), 3 * MAX_SIDE);
+    }
+
+    #[test]
+    fn geometry_tables() {
+        let classic = Geometry::new(Size::CLASSIC);
+        assert_eq!(&classic, Size::CLASSIC.geometry());
+        assert_eq!(classic.units().len(), 27);
+        for unit in classic.units() {
+            let mut cells = unit.clone();
+            cells.sort_unstable();
+            cells.dedup();
+            assert_eq!(cells.len(), 9);
+        }
+        for cell in 0..81 {
+            assert_eq!(classic.peers(cell).len(), 20);
+            assert!(!classic.peers(cell).contains(&cell));
+        }
+        // Peers in a row and a col, plus the rest of the box: 2 * 5 + 2 * 1 on a 6x6 board
+        let wide = Geometry::new(Size::new(3, 2).unwrap());
+        assert_eq!(wide.units().len(), 18);
+        assert!((0..36).all(|cell| wide.peers(cell).len() == 12));
+    }
+
+    #[test]
+    fn classic_tables_match_geometry() {
+        let geometry = Size::CLASSIC.geometry();
+        assert_eq!(classic_cells().len(), 27);
+        for (unit, cells) in classic_cells().iter().enumerate() {
+            let expected: Vec<u8> = geometry.units()[unit].iter().map(|&c| c as u8).collect();
+            assert_eq!(cells.to_vec(), expected);
+        }
+        for (cell, peers) in classic_peers().iter().enumerate() {
+            let expected: Vec<u8> = geometry.peers(cell).iter().map(|&c| c as u8).collect();
+            assert_eq!(peers.to_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn row_and_col_cells_match_start_offsets() {
+        for id in 0..9u8 {
+            let row_start = row_start_from_idx(id);
+            let col_start = col_start_from_idx(id);
+            let row: Vec<u8> = (0..3)
+                .flat_map(|b| (0..3).map(move |c| row_start + c + b * 9))
+                .collect();
+            let col: Vec<u8> = (0..3)
+                .flat_map(|b| (0..3).map(move |c| col_start + c * 3 + b * 27))
+                .collect();
+            assert_eq!(get_row_cells(id).to_vec(), row, "row {}", id);
+            assert_eq!(get_col_cells(id).to_vec(), col, "col {}", id);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! The `Candidates` type, the pencil marks of one cell as a bit set
+//!
+//! Value `v` is bit `v - 1`, so the marks `{1,2,9}` are stored as `0b1_0000_0011`. Values go up to
+//! `MAX_SIDE` so the same type holds the marks of every board size.
+
+use crate::board_ctrl::MAX_SIDE;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Sub, SubAssign};
+
+/// A set of values 1-25
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Candidates(u32);
+
+impl Candidates {
+    /// No values
+    pub const NONE: Candidates = Candidates(0);
+
+    /// Every value from 1 up to `side`, the marks of an empty cell with no filled peers
+    ///
+    /// Parameters:
+    /// - `side: usize` - largest value, 9 on a classic board
+    pub const fn all(side: usize) -> Candidates {
+        Candidates((1 << side) - 1)
+    }
+
+    /// Build a set straight from its bits, bits past value `MAX_SIDE` are dropped
+    pub const fn from_bits(bits: u32) -> Candidates {
+        Candidates(bits & Candidates::all(MAX_SIDE).0)
+    }
+
+    /// The raw bits of the set
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// A set holding only `value`
+    pub const fn only(value: u8) -> Candidates {
+        Candidates(1 << (value - 1))
+    }
+
+    /// Check if `value` is in the set
+    pub const fn contains(self, value: u8) -> bool {
+        self.0 & (1 << (value - 1)) != 0
+    }
+
+    /// Add `value` to the set
+    pub fn insert(&mut self, value: u8) {
+        self.0 |= 1 << (value - 1);
+    }
+
+    /// Take `value` out of the set
+    pub fn remove(&mut self, value: u8) {
+        self.0 &= !(1 << (value - 1));
+    }
+
+    /// Number of values in the set
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Check if the set has no values
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The only value in the set, `None` if it holds none or several
+    pub const fn single(self) -> Option<u8> {
+        if self.0.count_ones() == 1 {
+            Some(self.0.trailing_zeros() as u8 + 1)
+        } else {
+            None
+        }
+    }
+
+    /// The smallest value in the set
+    pub const fn first(self) -> Option<u8> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as u8 + 1)
+        }
+    }
+
+    /// Values in the set, smallest first
+    pub fn iter(self) -> CandidatesIter {
+        CandidatesIter(self.0)
+    }
+}
+
+/// Iterator over the values of a `Candidates`, smallest first
+#[derive(Debug, Clone)]
+pub struct CandidatesIter(u32);
+
+impl Iterator for CandidatesIter {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.0 == 0 {
+            return None;
+        }
+        let value = self.0.trailing_zeros() as u8 + 1;
+        self.0 &= self.0 - 1; // Clear the lowest bit
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for CandidatesIter {}
+
+impl IntoIterator for Candidates {
+    type Item = u8;
+    type IntoIter = CandidatesIter;
+
+    fn into_iter(self) -> CandidatesIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<u8> for Candidates {
+    fn from_iter<I: IntoIterator<Item = u8>>(values: I) -> Candidates {
+        let mut set = Candidates::NONE;
+        for value in values {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+/// Union
+impl BitOr for Candidates {
+    type Output = Candidates;
+
+    fn bitor(self, other: Candidates) -> Candidates {
+        Candidates(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Candidates {
+    fn bitor_assign(&mut self, other: Candidates) {
+        self.0 |= other.0;
+    }
+}
+
+/// Intersection
+impl BitAnd for Candidates {
+    type Output = Candidates;
+
+    fn bitand(self, other: Candidates) -> Candidates {
+        Candidates(self.0 & other.0)
+    }
+}
+
+impl BitAndAssign for Candidates {
+    fn bitand_assign(&mut self, other: Candidates) {
+        self.0 &= other.0;
+    }
+}
+
+/// Difference
+impl Sub for Candidates {
+    type Output = Candidates;
+
+    fn sub(self, other: Candidates) -> Candidates {
+        Candidates(self.0 & !other.0)
+    }
+}
+
+impl SubAssign for Candidates {
+    fn sub_assign(&mut self, other: Candidates) {
+        self.0 &= !other.0;
+    }
+}
+
+/// Formatted as a set, ex: `{1,4,7}`
+impl fmt::Display for Candidates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values: Vec<String> = self.iter().map(|v| v.to_string()).collect();
+        write!(f, "{{{}}}", values.join(","))
+    }
+}
+
+impl fmt::Debug for Candidates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_and_only() {
+        assert_eq!(Candidates::all(9).len(), 9);
+        assert_eq!(Candidates::all(25).iter().last(), Some(25));
+        assert!(!Candidates::all(9).contains(10));
+        assert_eq!(Candidates::only(4).single(), Some(4));
+        assert_eq!(Candidates::only(4).bits(), 0b1000);
+        assert_eq!(Candidates::from_bits(u32::MAX), Candidates::all(25));
+        assert!(Candidates::NONE.is_empty());
+        assert_eq!(Candidates::NONE.first(), None);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut marks = Candidates::NONE;
+        marks.insert(7);
+        marks.insert(2);
+        marks.insert(7);
+        assert_eq!(marks.len(), 2);
+        assert!(marks.contains(2) && marks.contains(7));
+        assert_eq!(marks.single(), None);
+        assert_eq!(marks.first(), Some(2));
+        marks.remove(2);
+        marks.remove(5);
+        assert_eq!(marks.single(), Some(7));
+    }
+
+    #[test]
+    fn iter_in_order() {
+        let marks: Candidates = [9, 1, 5].into_iter().collect();
+        assert_eq!(marks.iter().collect::<Vec<u8>>(), vec![1, 5, 9]);
+        assert_eq!(marks.iter().len(), 3);
+        assert_eq!(marks.into_iter().max(), Some(9));
+    }
+
+    #[test]
+    fn set_ops() {
+        let a: Candidates = [1, 2, 3].into_iter().collect();
+        let b: Candidates = [3, 4].into_iter().collect();
+        assert_eq!(a | b, [1, 2, 3, 4].into_iter().collect());
+        assert_eq!(a & b, Candidates::only(3));
+        assert_eq!(a - b, [1, 2].into_iter().collect());
+        let mut c = a;
+        c |= b;
+        c &= Candidates::all(3);
+        c -= Candidates::only(1);
+        assert_eq!(c, [2, 3].into_iter().collect());
+    }
+
+    #[test]
+    fn display() {
+        let marks: Candidates = [7, 1, 4].into_iter().collect();
+        assert_eq!(marks.to_string(), "{1,4,7}");
+        assert_eq!(format!("{:?}", Candidates::NONE), "{}");
+    }
+}
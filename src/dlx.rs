@@ -0,0 +1,252 @@
+//! Dancing Links (Knuth's Algorithm X) exact cover solver
+//!
+//! Every (cell, value) pair is a row of the cover matrix, filling 4 of its columns, one of each kind
+//! for every cell (324 columns on a classic board):
+//! - Cell: the cell holds some value
+//! - Row: the value appears in the cell's row
+//! - Col: the value appears in the cell's col
+//! - Box: the value appears in the cell's box
+//!
+//! A solution picks rows covering every column exactly once. The search keeps its own stack
+//! instead of recursing so it can stop after any solution and pick up again later.
+
+use crate::board_ctrl::Size;
+use std::iter::FusedIterator;
+
+/// Index of the root header, column headers follow it
+const ROOT: usize = 0;
+
+/// Exact cover matrix for one board, stored as circular doubly linked lists
+///
+/// Nodes `1..=4 * cells` are the column headers, every node after that belongs to a row
+pub struct DancingLinks {
+    board_size: Size,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Column header of each node
+    col: Vec<usize>,
+    /// (cell, value) that each node's row places
+    place: Vec<(usize, u8)>,
+    /// Rows left in each column, indexed by header
+    size: Vec<usize>,
+    /// One node of each row picked so far, deepest last
+    picked: Vec<usize>,
+    /// The picked rows currently form a solution that was already handed out
+    found: bool,
+    /// Every solution has been handed out
+    exhausted: bool,
+}
+
+impl DancingLinks {
+    /// Build the cover matrix for a board
+    /// Filled cells get the row for their value only, empty cells one row per value
+    /// A board that breaks the rules simply has no exact cover, and neither does a board with the
+    /// wrong number of cells for its size
+    ///
+    /// Parameters:
+    /// - `board: &[u8]` - reference to board vector
+    /// - `size: Size` - size of the board
+    pub fn new(board: &[u8], size: Size) -> DancingLinks {
+        // 4 constraints for each cell
+        let columns = 4 * size.cells();
+        let mut dlx = DancingLinks {
+            board_size: size,
+            left: Vec::new(),
+            right: Vec::new(),
+            up: Vec::new(),
+            down: Vec::new(),
+            col: Vec::new(),
+            place: Vec::new(),
+            size: vec![0; columns + 1],
+            picked: Vec::new(),
+            found: false,
+            exhausted: false,
+        };
+        for header in 0..=columns {
+            dlx.left
+                .push(if header == ROOT { columns } else { header - 1 });
+            dlx.right
+                .push(if header == columns { ROOT } else { header + 1 });
+            dlx.up.push(header);
+            dlx.down.push(header);
+            dlx.col.push(header);
+            dlx.place.push((0, 0));
+        }
+        if board.len() != size.cells() {
+            dlx.exhausted = true;
+            return dlx;
+        }
+        let side = size.side() as u8;
+        for (i, &cell) in board.iter().enumerate() {
+            // A value too large for the board leaves its cell with no row at all
+            let values = if cell == 0 {
+                1..side + 1
+            } else if cell <= side {
+                cell..cell + 1
+            } else {
+                0..0
+            };
+            for value in values {
+                dlx.add_row(i, value);
+            }
+        }
+        dlx
+    }
+
+    /// Append the row placing `value` in `cell`
+    fn add_row(&mut self, cell: usize, value: u8) {
+        let v = (value - 1) as usize;
+        let size = self.board_size;
+        let (side, cells) = (size.side(), size.cells());
+        let columns = [
+            cell,
+            cells + size.cell_row(cell) * side + v,
+            2 * cells + size.cell_col(cell) * side + v,
+            3 * cells + size.box_id(cell) * side + v,
+        ];
+        let first = self.col.len();
+        for (k, column) in columns.iter().enumerate() {
+            let header = column + 1;
+            let node = first + k;
+            self.left.push(if k == 0 { first + 3 } else { node - 1 });
+            self.right.push(if k == 3 { first } else { node + 1 });
+            // Link in at the bottom of the column
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let bottom = self.up[header];
+            self.down[bottom] = node;
+            self.up[header] = node;
+            self.col.push(header);
+            self.place.push((cell, value));
+            self.size[header] += 1;
+        }
+    }
+
+    /// Remove a column and every row using it
+    fn cover(&mut self, header: usize) {
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = r;
+        self.left[r] = l;
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.col[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    /// Undo `cover`, in exactly the reverse order
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.col[j]] += 1;
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (l, r) = (self.left[header], self.right[header]);
+        self.right[l] = header;
+        self.left[r] = header;
+    }
+
+    /// Pick a row, its own column is already covered
+    fn pick(&mut self, node: usize) {
+        self.picked.push(node);
+        let mut j = self.right[node];
+        while j != node {
+            self.cover(self.col[j]);
+            j = self.right[j];
+        }
+    }
+
+    /// Drop picked rows until one can move on to the next row in its column
+    /// Returns false once every choice has been tried
+    fn backtrack(&mut self) -> bool {
+        while let Some(node) = self.picked.pop() {
+            let mut j = self.left[node];
+            while j != node {
+                self.uncover(self.col[j]);
+                j = self.left[j];
+            }
+            let header = self.col[node];
+            if self.down[node] != header {
+                self.pick(self.down[node]);
+                return true;
+            }
+            self.uncover(header);
+        }
+        false
+    }
+
+    /// Column with the fewest rows left, the most constrained choice
+    fn smallest_column(&self) -> usize {
+        let mut best = self.right[ROOT];
+        let mut header = self.right[best];
+        while header != ROOT {
+            if self.size[header] < self.size[best] {
+                best = header;
+            }
+            header = self.right[header];
+        }
+        best
+    }
+
+    /// Search on for the next solution, `None` once there are no more
+    pub fn next_solution(&mut self) -> Option<Vec<u8>> {
+        if self.exhausted {
+            return None;
+        }
+        if self.found {
+            self.found = false;
+            if !self.backtrack() {
+                self.exhausted = true;
+                return None;
+            }
+        }
+        loop {
+            if self.right[ROOT] == ROOT {
+                self.found = true;
+                let mut board = vec![0; self.board_size.cells()];
+                for &node in &self.picked {
+                    let (cell, value) = self.place[node];
+                    board[cell] = value;
+                }
+                return Some(board);
+            }
+            let header = self.smallest_column();
+            if self.size[header] == 0 {
+                if !self.backtrack() {
+                    self.exhausted = true;
+                    return None;
+                }
+                continue;
+            }
+            self.cover(header);
+            self.pick(self.down[header]);
+        }
+    }
+}
+
+/// Solutions one at a time, each found only when asked for
+impl Iterator for DancingLinks {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        self.next_solution()
+    }
+}
+
+impl FusedIterator for DancingLinks {}
@@ -0,0 +1,9 @@
+pub mod board;
+pub mod board_ctrl;
+pub mod candidates;
+pub mod dlx;
+pub mod sat;
+pub mod solver;
+pub mod symbols;
+pub mod techniques;
+pub mod validation;
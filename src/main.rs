@@ -1,81 +1,94 @@
-mod board_ctrl;
-mod solver;
-mod validation;
+use sudoku_solver::board_ctrl::*;
+use sudoku_solver::solver::*;
+use sudoku_solver::techniques::Pipeline;
+use sudoku_solver::validation::*;
 
-use board_ctrl::*;
-use solver::*;
-use validation::*;
-
-// TODO Add board pencil, elim, write loop.
 // Board should always be immutable within this function. Any board parameters should be refs
+// The solver loops over its passes until the board is solved or it can go no further:
 // Pencil will mark possibilities,
-// Eliminate will try advanced strategies to remove possibilies (WIP)
+// Eliminate will run the technique pipeline to remove possibilies
 // Write in will take all marks and board state, build new board, and transfer ownership to board var
-// Then loop again to start over.
 fn main() {
     println!("START");
-    let board = read_board_file("board.txt");
+    let board = match read_board_file("board.txt") {
+        Ok(board) => board,
+        Err(err) => {
+            println!("Bad board.txt: {}", err);
+            return;
+        }
+    };
     // println!("Board: {:?}", board);
+    let size = board.size();
     println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-    print_board(&board);
-    println!("Valid: {}", board_validation(&board));
+    print!("{}", board);
+    println!("Valid: {}", board_validation(&board, size));
+    println!("Unique: {}", has_unique_solution(&board, size));
     println!("%%%%%%%%%%%%%%%%%%%%%%%%");
 
-    println!("Starting to write new board");
-    let pencil_marks = pencil_in(&board);
-    let new_board = write_in(&board, &pencil_marks);
-    // println!("Board: {:?}", new_board);
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-    print_board(&new_board);
-    println!("Valid: {}", board_validation(&new_board));
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
+    // Uniqueness techniques are only safe for puzzles known to have a single solution
+    let pipeline = if std::env::args().any(|arg| arg == "--unique") {
+        Pipeline::with_uniqueness(board.givens())
+    } else {
+        Pipeline::default()
+    };
 
-    println!("Iteration 2");
-    let pencil_marks_2 = pencil_in(&new_board);
-    let new_board_2 = write_in(&new_board, &pencil_marks_2);
-    // println!("Board: {:?}", new_board_2);
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-    print_board(&new_board_2);
-    println!("Valid: {}", board_validation(&new_board_2));
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-
-    println!("Iteration 3");
-    let pencil_marks_3 = pencil_in(&new_board_2);
-    let new_board_3 = write_in(&new_board_2, &pencil_marks_3);
-    // println!("Board: {:?}", new_board_3);
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-    print_board(&new_board_3);
-    println!("Valid: {}", board_validation(&new_board_3));
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
+    // Search used if logic alone can't finish the board
+    let backend = if std::env::args().any(|arg| arg == "--dlx") {
+        Backend::DancingLinks
+    } else if std::env::args().any(|arg| arg == "--sat") {
+        Backend::Sat
+    } else {
+        Backend::for_size(size)
+    };
 
-    println!("Iteration 4");
-    let pencil_marks_4 = pencil_in(&new_board_3);
-    let new_board_4 = write_in(&new_board_3, &pencil_marks_4);
-    // println!("Board: {:?}", new_board_4);
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-    print_board(&new_board_4);
-    println!("Valid: {}", board_validation(&new_board_4));
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-
-    println!("Iteration 5");
-    let pencil_marks_5 = pencil_in(&new_board_4);
-    let new_board_5 = write_in(&new_board_4, &pencil_marks_5);
-    // println!("Board: {:?}", new_board_5);
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-    print_board(&new_board_5);
-    println!("Valid: {}", board_validation(&new_board_5));
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-
-    println!("Iteration 6");
-    let pencil_marks_6 = pencil_in(&new_board_5);
-    let new_board_6 = write_in(&new_board_5, &pencil_marks_6);
-    // println!("Board: {:?}", new_board_6);
-    println!("%%%%%%%%%%%%%%%%%%%%%%%%");
-    print_board(&new_board_6);
-    println!("Valid: {}", board_validation(&new_board_6));
+    println!("Starting to write new board");
+    let (result, steps) = solve_steps(&board, size, &pipeline);
+    for step in &steps {
+        println!("{}", step.describe(size));
+    }
+    match result {
+        SolveResult::Solved(new_board) => {
+            println!("Solved");
+            println!("%%%%%%%%%%%%%%%%%%%%%%%%");
+            print_board(&new_board, size);
+            println!("Valid: {}", board_validation(&new_board, size));
+        }
+        SolveResult::Stalled {
+            board: new_board, ..
+        } => {
+            println!("Stalled, no more values can be written in");
+            println!("%%%%%%%%%%%%%%%%%%%%%%%%");
+            print_board(&new_board, size);
+            println!("Valid: {}", board_validation(&new_board, size));
+            // Logic alone can't finish the board, fall back to guessing
+            println!("%%%%%%%%%%%%%%%%%%%%%%%%");
+            match brute_force_with(&new_board, size, backend) {
+                SolveResult::Solved(guessed) => {
+                    println!("Solved by brute force");
+                    print_board(&guessed, size);
+                    println!("Valid: {}", board_validation(&guessed, size));
+                }
+                _ => println!("No solution"),
+            }
+        }
+        SolveResult::Contradiction {
+            board: new_board,
+            cell,
+        } => {
+            println!("Contradiction at {}", size.cell_name(cell));
+            println!("%%%%%%%%%%%%%%%%%%%%%%%%");
+            print_board(&new_board, size);
+            println!("Valid: {}", board_validation(&new_board, size));
+        }
+        SolveResult::NoSolution => println!("No solution"),
+    }
     println!("%%%%%%%%%%%%%%%%%%%%%%%%");
 
-    let solved = read_board_file("board_solved.txt");
-    print_board(&solved);
-    println!("Valid: {}", board_validation(&solved));
+    match read_board_file("board_solved.txt") {
+        Ok(solved) => {
+            print!("{}", solved);
+            println!("Valid: {}", board_validation(&solved, solved.size()));
+        }
+        Err(err) => println!("Bad board_solved.txt: {}", err),
+    }
 }
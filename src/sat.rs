@@ -0,0 +1,503 @@
+//! SAT encoding of a board and a small CDCL solver for it
+//!
+//! Variable `cell * side + value` (1 based, DIMACS style) is true when `value` goes in `cell`.
+//! The clauses say every cell holds exactly one value, every box|row|col holds every value
+//! exactly once, and every filled cell keeps its value.
+//!
+//! `to_dimacs` and `read_model` let an external solver be used for cross checking, ex:
+//! `minisat board.cnf board.model`, then decode the model file back into a board.
+
+use crate::board_ctrl::Size;
+
+/// Formula in conjunctive normal form, clauses of DIMACS literals
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cnf {
+    pub variables: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+/// Variables in the encoding of a board, one per (cell, value) pair
+///
+/// Parameters:
+/// - `size: Size` - size of the board
+pub fn variables(size: Size) -> usize {
+    size.cells() * size.side()
+}
+
+/// Variable that is true when `value` goes in `cell`
+///
+/// Parameters:
+/// - `size: Size` - size of the board
+/// - `cell: usize` - index of the cell
+/// - `value: u8` - value from 1 up to the side of the board
+pub fn cell_variable(size: Size, cell: usize, value: u8) -> i32 {
+    (cell * size.side()) as i32 + value as i32
+}
+
+/// Clauses saying exactly one of the variables is true
+fn exactly_one(clauses: &mut Vec<Vec<i32>>, variables: &[i32]) {
+    clauses.push(variables.to_vec());
+    for (i, &a) in variables.iter().enumerate() {
+        for &b in &variables[i + 1..] {
+            clauses.push(vec![-a, -b]);
+        }
+    }
+}
+
+impl Cnf {
+    /// Encode a board and the rules of sudoku
+    /// A value too large for the board, or a board with the wrong number of cells, becomes an
+    /// empty clause, which nothing satisfies
+    ///
+    /// Parameters:
+    /// - `board: &[u8]` - reference to board vector
+    /// - `size: Size` - size of the board
+    pub fn from_board(board: &[u8], size: Size) -> Cnf {
+        let side = size.side() as u8;
+        let mut clauses = Vec::new();
+        for cell in 0..size.cells() {
+            let values: Vec<i32> = (1..=side).map(|v| cell_variable(size, cell, v)).collect();
+            exactly_one(&mut clauses, &values);
+        }
+        for unit in size.geometry().units() {
+            for value in 1..=side {
+                let cells: Vec<i32> = unit
+                    .iter()
+                    .map(|&c| cell_variable(size, c, value))
+                    .collect();
+                exactly_one(&mut clauses, &cells);
+            }
+        }
+        if board.len() != size.cells() {
+            clauses.push(Vec::new());
+            return Cnf {
+                variables: variables(size),
+                clauses,
+            };
+        }
+        for (i, &value) in board.iter().enumerate() {
+            if value > side {
+                clauses.push(Vec::new());
+            } else if value != 0 {
+                clauses.push(vec![cell_variable(size, i, value)]);
+            }
+        }
+        Cnf {
+            variables: variables(size),
+            clauses,
+        }
+    }
+
+    /// Write the formula in DIMACS CNF format
+    pub fn to_dimacs(&self) -> String {
+        let mut text = format!("p cnf {} {}\n", self.variables, self.clauses.len());
+        for clause in &self.clauses {
+            for literal in clause {
+                text.push_str(&format!("{} ", literal));
+            }
+            text.push_str("0\n");
+        }
+        text
+    }
+
+    /// Read a formula in DIMACS CNF format
+    /// Comment lines start with `c`, clauses may span lines and each ends with `0`
+    ///
+    /// Parameters:
+    /// - `text: &str` - contents of a `.cnf` file
+    pub fn from_dimacs(text: &str) -> Result<Cnf, String> {
+        let mut variables = None;
+        let mut clauses = Vec::new();
+        let mut clause = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix("p cnf") {
+                let counts: Vec<&str> = header.split_whitespace().collect();
+                let parsed = counts.first().and_then(|v| v.parse::<usize>().ok());
+                if counts.len() != 2 || parsed.is_none() {
+                    return Err(format!("bad problem line: {}", line));
+                }
+                variables = parsed;
+                continue;
+            }
+            let Some(variables) = variables else {
+                return Err("clause before the problem line".to_string());
+            };
+            for token in line.split_whitespace() {
+                let literal: i32 = token
+                    .parse()
+                    .map_err(|_| format!("bad literal: {}", token))?;
+                if literal == 0 {
+                    clauses.push(std::mem::take(&mut clause));
+                } else if literal.unsigned_abs() as usize > variables {
+                    return Err(format!("literal {} is out of range", literal));
+                } else {
+                    clause.push(literal);
+                }
+            }
+        }
+        let Some(variables) = variables else {
+            return Err("missing problem line".to_string());
+        };
+        if !clause.is_empty() {
+            clauses.push(clause);
+        }
+        Ok(Cnf { variables, clauses })
+    }
+}
+
+/// Read the model written by an external SAT solver
+/// Accepts competition output (`s SATISFIABLE` with `v` lines) or minisat's result file.
+/// `None` when the solver reported the formula unsatisfiable
+///
+/// Parameters:
+/// - `text: &str` - solver output
+/// - `variables: usize` - variables in the formula that was solved
+pub fn read_model(text: &str, variables: usize) -> Result<Option<Vec<bool>>, String> {
+    let mut model = vec![false; variables];
+    for line in text.lines().map(str::trim) {
+        if line.contains("UNSAT") {
+            return Ok(None);
+        }
+        if line.is_empty() || line.starts_with('c') || line.starts_with('s') || line == "SAT" {
+            continue;
+        }
+        for token in line.trim_start_matches('v').split_whitespace() {
+            let literal: i32 = token
+                .parse()
+                .map_err(|_| format!("bad literal: {}", token))?;
+            let variable = literal.unsigned_abs() as usize;
+            if variable > variables {
+                return Err(format!("literal {} is out of range", literal));
+            }
+            if literal > 0 {
+                model[variable - 1] = true;
+            }
+        }
+    }
+    Ok(Some(model))
+}
+
+/// Turn a model of `Cnf::from_board` back into a board
+///
+/// Parameters:
+/// - `model: &[bool]` - truth value of each variable, variable 1 first
+/// - `size: Size` - size of the board
+pub fn decode_model(model: &[bool], size: Size) -> Vec<u8> {
+    (0..size.cells())
+        .map(|cell| {
+            (1..=size.side() as u8)
+                .find(|&v| model[cell_variable(size, cell, v) as usize - 1])
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Literal as an index, `variable * 2` when true and `variable * 2 + 1` when false (0 based)
+type Lit = usize;
+
+fn to_lit(literal: i32) -> Lit {
+    (literal.unsigned_abs() as usize - 1) * 2 + (literal < 0) as usize
+}
+
+/// Current value of a literal, `None` while its variable is unassigned
+fn lit_value(assigns: &[Option<bool>], lit: Lit) -> Option<bool> {
+    assigns[lit / 2].map(|value| value != (lit & 1 == 1))
+}
+
+/// Conflict driven clause learning solver state
+struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    /// Clauses watching each literal, visited when it becomes false
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    /// Clause that forced each variable, `None` for decisions
+    reason: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    /// Trail length at the start of each decision level
+    trail_lim: Vec<usize>,
+    /// Next trail entry to propagate
+    queue_head: usize,
+    activity: Vec<f64>,
+    bump: f64,
+}
+
+impl Solver {
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let variable = lit / 2;
+        self.assigns[variable] = Some(lit & 1 == 0);
+        self.level[variable] = self.decision_level();
+        self.reason[variable] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Add a clause with at least 2 literals, watching the first two
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0]].push(index);
+        self.watches[clause[1]].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    /// Unit propagation, returns a clause left with every literal false
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let false_lit = self.trail[self.queue_head] ^ 1;
+            self.queue_head += 1;
+            let mut watching = std::mem::take(&mut self.watches[false_lit]);
+            let mut conflict = None;
+            let mut i = 0;
+            while i < watching.len() {
+                let index = watching[i];
+                let clause = &mut self.clauses[index];
+                // Keep the false literal second
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if lit_value(&self.assigns, clause[0]) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                let replacement =
+                    (2..clause.len()).find(|&k| lit_value(&self.assigns, clause[k]) != Some(false));
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    self.watches[clause[1]].push(index);
+                    watching.swap_remove(i);
+                    continue;
+                }
+                let first = clause[0];
+                if lit_value(&self.assigns, first) == Some(false) {
+                    conflict = Some(index);
+                    break;
+                }
+                self.assign(first, Some(index));
+                i += 1;
+            }
+            self.watches[false_lit] = watching;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    fn bump_activity(&mut self, variable: usize) {
+        self.activity[variable] += self.bump;
+        if self.activity[variable] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.bump *= 1e-100;
+        }
+    }
+
+    /// First UIP conflict analysis
+    /// Returns the learnt clause, its asserting literal first, and the level to jump back to
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learnt = vec![0];
+        let mut seen = vec![false; self.assigns.len()];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut skip_first = false;
+        let uip = loop {
+            let literals = self.clauses[clause][skip_first as usize..].to_vec();
+            for lit in literals {
+                let variable = lit / 2;
+                if seen[variable] || self.level[variable] == 0 {
+                    continue;
+                }
+                seen[variable] = true;
+                self.bump_activity(variable);
+                if self.level[variable] == self.decision_level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            // Walk back to the latest literal in the conflict
+            loop {
+                index -= 1;
+                if seen[self.trail[index] / 2] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            seen[lit / 2] = false;
+            pending -= 1;
+            if pending == 0 {
+                break lit;
+            }
+            clause = self.reason[lit / 2].expect("implied literal without a reason");
+            skip_first = true;
+        };
+        learnt[0] = uip ^ 1;
+        let mut back_level = 0;
+        for k in 1..learnt.len() {
+            let level = self.level[learnt[k] / 2];
+            if level > back_level {
+                back_level = level;
+                learnt.swap(1, k);
+            }
+        }
+        (learnt, back_level)
+    }
+
+    fn backjump(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        for &lit in &self.trail[self.trail_lim[level]..] {
+            self.assigns[lit / 2] = None;
+            self.reason[lit / 2] = None;
+        }
+        self.trail.truncate(self.trail_lim[level]);
+        self.trail_lim.truncate(level);
+        self.queue_head = self.trail.len();
+    }
+
+    /// Most active unassigned variable, tried false first
+    fn decide(&self) -> Option<Lit> {
+        (0..self.assigns.len())
+            .filter(|&v| self.assigns[v].is_none())
+            .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]))
+            .map(|variable| variable * 2 + 1)
+    }
+}
+
+/// Solve a formula, `None` when it is unsatisfiable
+/// The model holds the truth value of each variable, variable 1 first
+///
+/// Parameters:
+/// - `cnf: &Cnf` - formula to solve
+pub fn solve_cnf(cnf: &Cnf) -> Option<Vec<bool>> {
+    let mut solver = Solver {
+        clauses: Vec::new(),
+        watches: vec![Vec::new(); cnf.variables * 2],
+        assigns: vec![None; cnf.variables],
+        level: vec![0; cnf.variables],
+        reason: vec![None; cnf.variables],
+        trail: Vec::new(),
+        trail_lim: Vec::new(),
+        queue_head: 0,
+        activity: vec![0.0; cnf.variables],
+        bump: 1.0,
+    };
+    for clause in &cnf.clauses {
+        let mut lits: Vec<Lit> = clause.iter().map(|&l| to_lit(l)).collect();
+        lits.sort_unstable();
+        lits.dedup();
+        // Always true, holds both a literal and its negation
+        if lits.windows(2).any(|pair| pair[0] ^ 1 == pair[1]) {
+            continue;
+        }
+        match lits.len() {
+            0 => return None,
+            1 => match lit_value(&solver.assigns, lits[0]) {
+                Some(false) => return None,
+                Some(true) => {}
+                None => solver.assign(lits[0], None),
+            },
+            _ => {
+                solver.attach(lits);
+            }
+        }
+    }
+    loop {
+        if let Some(conflict) = solver.propagate() {
+            if solver.decision_level() == 0 {
+                return None;
+            }
+            let (learnt, back_level) = solver.analyze(conflict);
+            solver.backjump(back_level);
+            let asserting = learnt[0];
+            if learnt.len() == 1 {
+                solver.assign(asserting, None);
+            } else {
+                let index = solver.attach(learnt);
+                solver.assign(asserting, Some(index));
+            }
+            solver.bump *= 1.0 / 0.95;
+            continue;
+        }
+        match solver.decide() {
+            Some(lit) => {
+                solver.trail_lim.push(solver.trail.len());
+                solver.assign(lit, None);
+            }
+            None => {
+                return Some(solver.assigns.iter().map(|a| *a == Some(true)).collect());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    fn sample() -> (Board, Board) {
+        let board: Board = include_str!("../board.txt").parse().unwrap();
+        let solved: Board = include_str!("../board_solved.txt").parse().unwrap();
+        (board, solved)
+    }
+
+    #[test]
+    fn dimacs_round_trip() {
+        let (board, _) = sample();
+        let cnf = Cnf::from_board(&board, board.size());
+        assert_eq!(Cnf::from_dimacs(&cnf.to_dimacs()), Ok(cnf));
+    }
+
+    #[test]
+    fn dimacs_comments_and_split_clauses() {
+        let cnf = Cnf::from_dimacs("c comment\np cnf 3 2\n1 -2\n0 3 0\n").unwrap();
+        assert_eq!(cnf.variables, 3);
+        assert_eq!(cnf.clauses, vec![vec![1, -2], vec![3]]);
+        assert!(Cnf::from_dimacs("1 2 0\n").is_err());
+        assert!(Cnf::from_dimacs("p cnf 2 1\n1 3 0\n").is_err());
+    }
+
+    #[test]
+    fn solves_known_board() {
+        let (board, solved) = sample();
+        let model = solve_cnf(&Cnf::from_board(&board, board.size())).unwrap();
+        assert_eq!(decode_model(&model, board.size()), solved.cells());
+    }
+
+    #[test]
+    fn unsat_board() {
+        let size = Size::CLASSIC;
+        let mut board = vec![0; size.cells()];
+        board[0] = 1;
+        board[1] = 1;
+        assert_eq!(solve_cnf(&Cnf::from_board(&board, size)), None);
+        board[1] = 10;
+        assert_eq!(solve_cnf(&Cnf::from_board(&board, size)), None);
+        assert_eq!(solve_cnf(&Cnf::from_board(&[0; 82], size)), None);
+        assert_eq!(solve_cnf(&Cnf::from_board(&[0; 80], size)), None);
+    }
+
+    #[test]
+    fn read_model_sat() {
+        let expected = Some(vec![true, false, true]);
+        let competition = "c solver\ns SATISFIABLE\nv 1 -2\nv 3 0\n";
+        assert_eq!(read_model(competition, 3), Ok(expected.clone()));
+        assert_eq!(read_model("SAT\n1 -2 3 0\n", 3), Ok(expected));
+        assert!(read_model("SAT\n1 -4 0\n", 3).is_err());
+    }
+
+    #[test]
+    fn read_model_unsat() {
+        assert_eq!(read_model("s UNSATISFIABLE\n", 3), Ok(None));
+        assert_eq!(read_model("UNSAT\n", 3), Ok(None));
+    }
+}
@@ -1,22 +1,115 @@
-use crate::board_ctrl::{
-    get_board_box, get_board_col, get_board_row, get_box_id, get_cell_col, get_cell_row, write_cell,
-};
+use crate::board_ctrl::{Geometry, Size, UnitKind};
+use crate::candidates::Candidates;
+use crate::dlx::DancingLinks;
+use crate::sat::{Cnf, decode_model, solve_cnf};
+use crate::techniques::{Pipeline, Step};
+use crate::validation::board_validation;
 
-/// Last free cell (3x3)
+/// Final state of the board once the solver can no longer continue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveResult {
+    /// Every cell is filled in
+    Solved(Vec<u8>),
+    /// A pass made no progress. Holds the partial board and its pencil marks
+    /// Boards the techniques don't handle stall as soon as singles run out, see `Technique`
+    Stalled {
+        board: Vec<u8>,
+        candidates: Vec<Candidates>,
+    },
+    /// The board can not be solved. `cell` is the first cell found breaking the rules
+    Contradiction { board: Vec<u8>, cell: usize },
+    /// Every possible value was tried and none of them solve the board
+    NoSolution,
+}
+
+/// Something the solver did to the board, see `solve_steps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveStep {
+    /// A value written in from a naked or hidden single
+    Write {
+        cell: usize,
+        value: u8,
+        hidden: bool,
+    },
+    /// Pencil marks removed, or values placed, by a technique
+    Eliminate(Step),
+}
+
+impl SolveStep {
+    /// One line report of the step, ex: `W- r1c2, val: 5 (hidden single)` or `E- X-Wing: ...`
+    ///
+    /// Parameters:
+    /// - `size: Size` - size of the board, used to name cells
+    pub fn describe(&self, size: Size) -> String {
+        match self {
+            SolveStep::Write {
+                cell,
+                value,
+                hidden: false,
+            } => format!("W- {}, val: {}", size.cell_name(*cell), value),
+            SolveStep::Write {
+                cell,
+                value,
+                hidden: true,
+            } => format!(
+                "W- {}, val: {} (hidden single)",
+                size.cell_name(*cell),
+                value
+            ),
+            SolveStep::Eliminate(step) => format!("E- {}: {}", step.technique, step.description),
+        }
+    }
+}
+
+/// Search used by `brute_force_with` to finish a board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Depth first search over pencil marks, following singles before each guess
+    /// Can still take minutes on 25x25 boards with many empty cells, see `Backend::for_size`
+    #[default]
+    Backtracking,
+    /// Knuth's Algorithm X over the exact cover matrix, see `dlx`
+    DancingLinks,
+    /// CDCL SAT solver over the CNF encoding, see `sat`
+    Sat,
+}
+
+impl Backend {
+    /// The backend that finishes boards of a size quickest
+    /// Backtracking up to 16x16, past that its guesses pile up and `DancingLinks` is far faster
+    ///
+    /// Parameters:
+    /// - `size: Size` - size of the board
+    pub fn for_size(size: Size) -> Backend {
+        if size.side() > 16 {
+            Backend::DancingLinks
+        } else {
+            Backend::Backtracking
+        }
+    }
+}
+
+/// Last free cell
 /// Finishes a cell by identifying the last missing value
 /// Assumes the board state is valid
 ///
 /// Parameters:
 /// - `board: &[u8]` - Reference to board vector
-/// - `box_id: u8` - Requested box ID from board to check for solution
-pub fn last_free_cell(board: &[u8], box_id: u8) -> Option<Vec<u8>> {
-    let box_to_check = get_board_box(board, box_id);
-    let zeros = box_to_check.iter().filter(|&x| *x == 0).count();
+/// - `size: Size` - size of the board
+/// - `box_id: usize` - Requested box ID from board to check for solution
+pub fn last_free_cell(board: &[u8], size: Size, box_id: usize) -> Option<Vec<u8>> {
+    let mut new_box: Vec<u8> = size
+        .geometry()
+        .unit(UnitKind::Box, box_id)
+        .iter()
+        .map(|&c| board[c])
+        .collect();
+    let zeros = new_box.iter().filter(|&x| *x == 0).count();
     if zeros == 1 {
-        let missing_val = (1u8..10u8).sum::<u8>() - box_to_check.iter().sum::<u8>();
-        let idx = box_to_check.iter().position(|&x| x == 0).unwrap();
-        let mut new_box = box_to_check.clone();
-        new_box[idx] = missing_val;
+        let filled: usize = new_box.iter().map(|&x| x as usize).sum();
+        let missing_val = (1..=size.side()).sum::<usize>() - filled;
+        let idx = new_box.iter().position(|&x| x == 0)?;
+        new_box[idx] = missing_val as u8;
         return Some(new_box);
     }
     None
@@ -28,100 +121,745 @@ pub fn last_free_cell(board: &[u8], box_id: u8) -> Option<Vec<u8>> {
 ///
 /// Parameters:
 /// - `board: &[u8]` - reference to board vector
-pub fn pencil_in(board: &[u8]) -> Vec<u16> {
-    let base: u16 = 2; // Working with binary flags
-    let possible: u16 = base.pow(9) - 1; // Fill a bit flag of 0000 0001 1111 1111
-    let mut penciled_values: Vec<u16> = Vec::new(); // Vector to store all possible states
+/// - `size: Size` - size of the board
+pub fn pencil_in(board: &[u8], size: Size) -> Vec<Candidates> {
+    let geometry = size.geometry();
+    let mut penciled_values: Vec<Candidates> = Vec::with_capacity(board.len()); // Vector to store all possible states
 
     // Loop through the full board space
-    for i in 0..board.len() {
-        // println!("*********** NEW CELL **********");  //debug
-        let cell = board.get(i).unwrap(); // Fetch cell
-        if *cell != 0 {
+    for (i, &cell) in board.iter().enumerate() {
+        if cell != 0 {
             // If the cell is already filled, skip
-            penciled_values.push(0);
-        } else {
-            // Otherwise, pencil in
-            let mut pencil_marks = possible.clone();
-            let c_box = get_board_box(board, get_box_id(i as u8));
-            let c_row = get_board_row(board, get_cell_row(i as u8));
-            let c_col = get_board_col(board, get_cell_col(i as u8));
-
-            // Skip this step if the value is 0
-            // All cells are assumed to have all possibilities
-            // Remove marks for found values using bitwise operations
-            // !(1 << box_cell -1) is used to get the inverse value of a given bit index
-            // By &'ing the value against pencil marks, that possibility is deactivated
-            // Ex: box_cell=3; 1111 1111 1111 1011 & 0000 0001 1111 1111 = 0000 0001 1111 1011
-            for box_cell in c_box {
-                if box_cell != 0 {
-                    // println!("Found value {}", box_cell);
-                    pencil_marks = !(1 << box_cell - 1) & pencil_marks;
-                    // println!("New pencil marks: {:09b}", pencil_marks);
-                }
+            penciled_values.push(Candidates::NONE);
+            continue;
+        }
+        // Otherwise, pencil in
+        // All cells are assumed to have all possibilities, the values of its peers are removed
+        let mut pencil_marks = Candidates::all(size.side());
+        for &peer in geometry.peers(i) {
+            let peer_value = board[peer];
+            if peer_value != 0 {
+                pencil_marks.remove(peer_value);
+            }
+        }
+        penciled_values.push(pencil_marks);
+    }
+    penciled_values
+}
+
+/// Values ready to be written in, as `(cell idx, value)`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Singles {
+    /// Cells with a single pencil mark left
+    pub naked: Vec<(usize, u8)>,
+    /// Values with a single place left in a box|row|col
+    pub hidden: Vec<(usize, u8)>,
+}
+
+/// A board together with its pencil marks, kept in step as the board changes
+/// Writing a value in only clears that value from the cell's peers instead of running
+/// `pencil_in` over the whole board again. Cells whose marks changed are remembered so that
+/// singles are only looked for around them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateState {
+    geometry: &'static Geometry,
+    board: Vec<u8>,
+    pencil_values: Vec<Candidates>,
+    /// Empty cells left to fill
+    empty: usize,
+    /// Cells whose pencil marks changed since singles were last taken
+    changed: Vec<usize>,
+    /// Flags for `changed`, indexed by cell
+    is_changed: Vec<bool>,
+    /// First cell found breaking the rules
+    contradiction: Option<usize>,
+}
+
+impl CandidateState {
+    /// Pencil in a board once to start from
+    ///
+    /// Parameters:
+    /// - `board: &[u8]` - reference to board vector
+    /// - `size: Size` - size of the board
+    pub fn new(board: &[u8], size: Size) -> CandidateState {
+        CandidateState::with_pencil_values(board, &pencil_in(board, size), size)
+    }
+
+    /// Start from pencil marks already worked out, ex: after a technique removed some
+    ///
+    /// Parameters:
+    /// - `board: &[u8]` - reference to board vector
+    /// - `pencil_values: &[Candidates]` - reference to the board's pencil marks
+    /// - `size: Size` - size of the board
+    pub fn with_pencil_values(
+        board: &[u8],
+        pencil_values: &[Candidates],
+        size: Size,
+    ) -> CandidateState {
+        CandidateState {
+            geometry: size.geometry(),
+            board: board.to_vec(),
+            empty: board.iter().filter(|&&v| v == 0).count(),
+            changed: (0..board.len()).collect(),
+            is_changed: vec![true; board.len()],
+            contradiction: find_contradiction(board, pencil_values, size),
+            pencil_values: pencil_values.to_vec(),
+        }
+    }
+
+    /// Size of the board
+    pub fn size(&self) -> Size {
+        self.geometry.size()
+    }
+
+    /// Current board
+    pub fn board(&self) -> &[u8] {
+        &self.board
+    }
+
+    /// Current pencil marks
+    pub fn pencil_values(&self) -> &[Candidates] {
+        &self.pencil_values
+    }
+
+    /// First cell found that makes the board unsolvable, see `find_contradiction`
+    pub fn contradiction(&self) -> Option<usize> {
+        self.contradiction
+    }
+
+    /// Check if every cell is filled in
+    pub fn is_solved(&self) -> bool {
+        self.empty == 0
+    }
+
+    fn mark_changed(&mut self, cell_id: usize) {
+        if !self.is_changed[cell_id] {
+            self.is_changed[cell_id] = true;
+            self.changed.push(cell_id);
+        }
+    }
+
+    /// Remove a single pencil mark, an empty cell left with none is a contradiction
+    ///
+    /// Parameters:
+    /// - `cell_id: usize` - index of the cell
+    /// - `value: u8` - value to remove
+    pub fn eliminate(&mut self, cell_id: usize, value: u8) {
+        if !self.pencil_values[cell_id].contains(value) {
+            return;
+        }
+        self.pencil_values[cell_id].remove(value);
+        self.mark_changed(cell_id);
+        if self.pencil_values[cell_id].is_empty() && self.board[cell_id] == 0 {
+            self.contradiction.get_or_insert(cell_id);
+        }
+    }
+
+    /// Write a value into an empty cell and clear it from the pencil marks of its peers
+    /// Writing a value that isn't pencilled in is a contradiction, filled cells are left as they are
+    ///
+    /// Parameters:
+    /// - `cell_id: usize` - index of the cell
+    /// - `value: u8` - value to write
+    pub fn write_cell(&mut self, cell_id: usize, value: u8) {
+        if self.board[cell_id] != 0 {
+            return;
+        }
+        if !self.pencil_values[cell_id].contains(value) {
+            self.contradiction.get_or_insert(cell_id);
+        }
+        self.board[cell_id] = value;
+        self.pencil_values[cell_id] = Candidates::NONE;
+        self.empty -= 1;
+        self.mark_changed(cell_id);
+        let geometry = self.geometry;
+        for &peer in geometry.peers(cell_id) {
+            self.eliminate(peer, value);
+        }
+    }
+
+    /// Apply a technique step, like `Step::apply`
+    /// Only the marks in `Step::removals` are removed, so a step changes the state exactly when
+    /// `Step::makes_progress` says it does
+    ///
+    /// Parameters:
+    /// - `step: &Step` - step found by a technique
+    pub fn apply(&mut self, step: &Step) {
+        for (cell_id, value) in step.removals(&self.pencil_values) {
+            self.eliminate(cell_id as usize, value);
+        }
+    }
+
+    /// Find the naked and hidden singles made by changes since the last call
+    /// Only the changed cells, and the units they belong to, are searched
+    pub fn take_singles(&mut self) -> Singles {
+        let geometry = self.geometry;
+        let mut naked: Vec<(usize, u8)> = Vec::new();
+        let mut units = vec![false; geometry.units().len()];
+        for cell_id in std::mem::take(&mut self.changed) {
+            self.is_changed[cell_id] = false;
+            if let Some(value) = self.pencil_values[cell_id].single() {
+                naked.push((cell_id, value));
             }
-            for row_cell in c_row {
-                if row_cell != 0 {
-                    // println!("Found value {}", row_cell);
-                    pencil_marks = !(1 << row_cell - 1) & pencil_marks;
-                    // println!("New pencil marks: {:09b}", pencil_marks);
+            for unit_id in geometry.cell_units(cell_id) {
+                units[unit_id] = true;
+            }
+        }
+        let mut hidden: Vec<(usize, u8)> = Vec::new();
+        let side = geometry.size().side() as u8;
+        for (unit, _) in geometry
+            .units()
+            .iter()
+            .zip(units)
+            .filter(|&(_, touched)| touched)
+        {
+            for value in 1..=side {
+                let mut marked = unit
+                    .iter()
+                    .filter(|&&cell_id| self.pencil_values[cell_id].contains(value));
+                if let (Some(&cell_id), None) = (marked.next(), marked.next())
+                    && !hidden.iter().any(|&(c, _)| c == cell_id)
+                {
+                    hidden.push((cell_id, value));
                 }
             }
-            for col_cell in c_col {
-                if col_cell != 0 {
-                    // println!("Found value {}", col_cell);
-                    pencil_marks = !(1 << col_cell - 1) & pencil_marks;
-                    // println!("New pencil marks: {:09b}", pencil_marks);
+        }
+        Singles { naked, hidden }
+    }
+
+    /// Write in singles, naked ones first
+    /// A hidden single is skipped if a naked single already filled its cell
+    /// Returns the singles that were written
+    ///
+    /// Parameters:
+    /// - `singles: Singles` - values to write, ex: from `take_singles`
+    pub fn write_singles(&mut self, singles: Singles) -> Singles {
+        for &(cell_id, value) in &singles.naked {
+            self.write_cell(cell_id, value);
+        }
+        let mut hidden = Vec::with_capacity(singles.hidden.len());
+        for (cell_id, value) in singles.hidden {
+            if self.board[cell_id] == 0 {
+                self.write_cell(cell_id, value);
+                hidden.push((cell_id, value));
+            }
+        }
+        Singles {
+            naked: singles.naked,
+            hidden,
+        }
+    }
+}
+
+/// Find naked singles
+/// Cells that have only a single pencil mark remaining, as `(cell idx, value)`
+///
+/// Parameters:
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+pub fn naked_singles(pencil_values: &[Candidates]) -> Vec<(usize, u8)> {
+    pencil_values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, mark)| mark.single().map(|value| (i, value)))
+        .collect()
+}
+
+/// Find hidden singles
+/// Values marked in only one cell of a box|row|col must go in that cell, as `(cell idx, value)`
+/// A cell found through several units is only listed once
+///
+/// Parameters:
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+/// - `size: Size` - size of the board
+pub fn hidden_singles(pencil_values: &[Candidates], size: Size) -> Vec<(usize, u8)> {
+    let geometry = size.geometry();
+    let mut singles: Vec<(usize, u8)> = Vec::new();
+    for unit_id in 0..size.side() {
+        for kind in [UnitKind::Box, UnitKind::Row, UnitKind::Col] {
+            for value in 1..=size.side() as u8 {
+                let mut marked = geometry
+                    .unit(kind, unit_id)
+                    .iter()
+                    .filter(|&&cell_id| pencil_values[cell_id].contains(value));
+                if let (Some(&cell_id), None) = (marked.next(), marked.next())
+                    && !singles.iter().any(|&(c, _)| c == cell_id)
+                {
+                    singles.push((cell_id, value));
                 }
             }
-            penciled_values.push(pencil_marks);
         }
     }
-    // Return vector of all cell possibilities
-    penciled_values
+    singles
 }
 
-//TODO Implement advanced solving techniques to minimize possible values
-// May not need board, may just be possible given pencil marks. TBD. Returns new pencil marks
-//pub fn eliminate(board: &[u8], pencil_values: &[u16]) -> Vec<u16> {}
+/// Eliminate pencil marks
+/// Run the techniques of the pipeline in order and apply the first step that makes progress
+/// Returns the new pencil marks, unchanged if no technique found anything
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to current board
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+/// - `pipeline: &Pipeline` - techniques to try, in order
+pub fn eliminate(
+    board: &[u8],
+    pencil_values: &[Candidates],
+    pipeline: &Pipeline,
+) -> Vec<Candidates> {
+    match pipeline.find_step(board, pencil_values) {
+        Some(step) => step.apply(pencil_values),
+        None => pencil_values.to_vec(),
+    }
+}
 
 /// Write in value
-/// Evaluate current pencil marks, fill ones that have only a single value remaining
+/// Evaluate current pencil marks, fill ones that have only a single value remaining (naked single)
+/// and values that only have a single pencil mark left in a box|row|col (hidden single)
+/// The singles are written through a `CandidateState`, the same way the `solve` loop writes them
 ///
 /// Parameters:
 /// - `board: &[u8]` - reference to current board
-/// - `pencil_values: &[u16]` - reference to current pencil marks
-pub fn write_in(board: &[u8], pencil_values: &[u16]) -> Vec<u8> {
-    let mut new_board: Vec<u8> = board.to_vec(); // Use a copy of the old board
-    for i in 0..pencil_values.len() {
-        // Loop over all cells
-        let cell: &u16 = pencil_values.get(i).unwrap(); // Fetch a given cell pencil mark
-        if *cell != 0 {
-            // 0 indicates that this cell has no pencil marks
-            // println!("CELLIDX: {}, PENMARK: {:09b}", i, cell); // DEBUG
-            let mut bit: u16 = 1; // Bit map to use to identify which are set
-            let mut valset: u16 = 0; // A tracker to see if a given cell will be written in
-            while bit < 0b1000000000 {
-                // While the bits are < 10 bit position, there are still flags
-                if bit & *cell != 0 && valset == 0 {
-                    // Found flag and valset has never been set
-                    // println!("Possible fill in identified: {}", bit.ilog2());   // debug
-                    valset = bit.ilog2() as u16 + 1; // Value to write is 1 more than the position identified by log_2
-                } else if bit & *cell != 0 && valset != 0 {
-                    // Found flag with valset. This cell has more than 1 value
-                    // println!(                                                    //debug
-                    //     "More than one possibility remains for this cell. {}",   // debug
-                    //     bit.ilog2()                                              //debug
-                    // );                                                           //debug
-                    valset = 0xFFFF; // Set duplicate flag indicator
-                }
-                bit <<= 1; // Move up the bits
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+/// - `size: Size` - size of the board
+pub fn write_in(board: &[u8], pencil_values: &[Candidates], size: Size) -> Vec<u8> {
+    let mut state = CandidateState::with_pencil_values(board, pencil_values, size);
+    let singles = state.take_singles();
+    state.write_singles(singles);
+    state.board
+}
+
+/// Find a cell that makes the board unsolvable
+/// Either a filled cell that repeats a value in its box|row|col, or an empty cell with no pencil marks left
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to current board
+/// - `pencil_values: &[Candidates]` - reference to pencil marks of current board
+/// - `size: Size` - size of the board
+pub fn find_contradiction(board: &[u8], pencil_values: &[Candidates], size: Size) -> Option<usize> {
+    let geometry = size.geometry();
+    for (cell_id, cell) in board.iter().enumerate() {
+        if *cell == 0 {
+            if pencil_values[cell_id].is_empty() {
+                return Some(cell_id);
             }
-            if valset != 0xFFFF {
-                println!("W- idx: {}, val: {}", i, valset);
-                new_board = write_cell(&new_board, i as u8, valset as u8);
+            continue;
+        }
+        // A filled cell repeats its value if any of its peers holds it too
+        if geometry
+            .peers(cell_id)
+            .iter()
+            .any(|&peer| board[peer] == *cell)
+        {
+            return Some(cell_id);
+        }
+    }
+    None
+}
+
+/// Solve the board with every available technique
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to starting board
+/// - `size: Size` - size of the board
+pub fn solve(board: &[u8], size: Size) -> SolveResult {
+    solve_with(board, size, &Pipeline::default())
+}
+
+/// Solve the board
+/// Repeat the write in and eliminate passes until the board is solved, a pass makes no progress,
+/// or the board reaches a contradiction
+/// Pencil marks are only worked out once, then kept up to date by `CandidateState` as values are
+/// written in and marks eliminated, so each pass only looks at what changed
+/// The pipeline is only run on the boards the techniques handle (see `Technique`), the rest is
+/// left to `brute_force_with`
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to starting board
+/// - `size: Size` - size of the board
+/// - `pipeline: &Pipeline` - techniques used by the eliminate pass, in order
+pub fn solve_with(board: &[u8], size: Size, pipeline: &Pipeline) -> SolveResult {
+    solve_steps(board, size, pipeline).0
+}
+
+/// Solve the board like `solve_with`, also returning every step taken on the way, in order
+/// Eliminations only show up on boards the pipeline is run on, see `Technique`
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to starting board
+/// - `size: Size` - size of the board
+/// - `pipeline: &Pipeline` - techniques used by the eliminate pass, in order
+pub fn solve_steps(board: &[u8], size: Size, pipeline: &Pipeline) -> (SolveResult, Vec<SolveStep>) {
+    let mut state = CandidateState::new(board, size);
+    let mut steps: Vec<SolveStep> = Vec::new();
+    loop {
+        if let Some(cell) = state.contradiction() {
+            let result = SolveResult::Contradiction {
+                board: state.board,
+                cell,
+            };
+            return (result, steps);
+        }
+        if state.is_solved() {
+            return (SolveResult::Solved(state.board), steps);
+        }
+        let singles = state.take_singles();
+        if !singles.naked.is_empty() || !singles.hidden.is_empty() {
+            let written = state.write_singles(singles);
+            for (cell, value) in written.naked {
+                steps.push(SolveStep::Write {
+                    cell,
+                    value,
+                    hidden: false,
+                });
+            }
+            for (cell, value) in written.hidden {
+                steps.push(SolveStep::Write {
+                    cell,
+                    value,
+                    hidden: true,
+                });
+            }
+            continue;
+        }
+        let step = if size == Size::CLASSIC {
+            pipeline.find_step(&state.board, &state.pencil_values)
+        } else {
+            None
+        };
+        match step {
+            Some(step) => {
+                state.apply(&step);
+                steps.push(SolveStep::Eliminate(step));
+            }
+            None => {
+                let result = SolveResult::Stalled {
+                    board: state.board,
+                    candidates: state.pencil_values,
+                };
+                return (result, steps);
+            }
+        }
+    }
+}
+
+/// Brute force
+/// Depth first search over the pencil marks, used once the logical passes stall
+/// Always fills the cell with the fewest pencil marks next, backtracking when a guess breaks the rules
+/// Boards past 16x16 use dancing links instead, see `Backend::for_size`
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to starting board
+/// - `size: Size` - size of the board
+pub fn brute_force(board: &[u8], size: Size) -> SolveResult {
+    brute_force_with(board, size, Backend::for_size(size))
+}
+
+/// Brute force with the chosen search backend
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to starting board
+/// - `size: Size` - size of the board
+/// - `backend: Backend` - search used to fill the board
+pub fn brute_force_with(board: &[u8], size: Size, backend: Backend) -> SolveResult {
+    if !board_validation(board, size) {
+        return SolveResult::NoSolution;
+    }
+    let solved = match backend {
+        Backend::Backtracking => backtrack(board, size),
+        Backend::DancingLinks => DancingLinks::new(board, size).next_solution(),
+        Backend::Sat => {
+            solve_cnf(&Cnf::from_board(board, size)).map(|model| decode_model(&model, size))
+        }
+    };
+    match solved {
+        Some(solved) => SolveResult::Solved(solved),
+        None => SolveResult::NoSolution,
+    }
+}
+
+/// Try each pencil mark of the most constrained cell and recurse, `None` when no value works
+fn backtrack(board: &[u8], size: Size) -> Option<Vec<u8>> {
+    search(CandidateState::new(board, size))
+}
+
+/// Write in every naked and hidden single, then guess and recurse on a copy of the state
+/// Following the singles before guessing keeps the search small
+fn search(mut state: CandidateState) -> Option<Vec<u8>> {
+    let size = state.size();
+    loop {
+        let singles = state.take_singles();
+        if singles == Singles::default() {
+            break;
+        }
+        state.write_singles(singles);
+    }
+    // Singles written together may clash, ex: two cells left with the same value in a row
+    if state.contradiction().is_some() || !board_validation(state.board(), size) {
+        return None;
+    }
+    let (board, pencil_values) = (state.board(), state.pencil_values());
+    let cell = (0..board.len())
+        .filter(|&i| board[i] == 0)
+        .min_by_key(|&i| pencil_values[i].len());
+    let Some(cell) = cell else {
+        // No empty cells left, and the board was validated above
+        return Some(state.board);
+    };
+    for value in pencil_values[cell] {
+        let mut guess = state.clone();
+        guess.write_cell(cell, value);
+        if let Some(solved) = search(guess) {
+            return Some(solved);
+        }
+    }
+    None
+}
+
+/// Every solution of a board, searched for lazily
+/// Nothing is solved until the iterator is advanced, so it is fine to stop at any point, ex:
+/// `solutions(&board, size).take(10)` on a nearly empty board
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to board vector
+/// - `size: Size` - size of the board
+pub fn solutions(board: &[u8], size: Size) -> DancingLinks {
+    DancingLinks::new(board, size)
+}
+
+/// Count the solutions of a board, stopping once `limit` have been found
+/// Counting up to 2 is enough to tell a proper puzzle from an ambiguous one
+/// A board with the wrong number of cells for its size has none
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to board vector
+/// - `size: Size` - size of the board
+/// - `limit: usize` - most solutions to look for
+pub fn count_solutions(board: &[u8], size: Size, limit: usize) -> usize {
+    solutions(board, size).take(limit).count()
+}
+
+/// Check that a board is a proper puzzle, with exactly one solution
+///
+/// Parameters:
+/// - `board: &[u8]` - reference to board vector
+/// - `size: Size` - size of the board
+pub fn has_unique_solution(board: &[u8], size: Size) -> bool {
+    count_solutions(board, size, 2) == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    /// A hard puzzle with a single solution, row by row
+    const HARD: &str =
+        "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+
+    /// Read a classic board written row by row, `.` for empty cells
+    fn row_major(text: &str) -> Vec<u8> {
+        let size = Size::CLASSIC;
+        let mut board = vec![0; size.cells()];
+        for (i, c) in text.chars().enumerate() {
+            board[size.cell_id(i / 9, i % 9)] = c.to_digit(10).unwrap_or(0) as u8;
+        }
+        board
+    }
+
+    #[test]
+    fn backends_agree_on_hard_puzzle() {
+        let size = Size::CLASSIC;
+        let board = row_major(HARD);
+        let SolveResult::Solved(solved) = brute_force_with(&board, size, Backend::Backtracking)
+        else {
+            panic!("backtracking found no solution");
+        };
+        assert!(board_validation(&solved, size) && !solved.contains(&0));
+        assert!(board.iter().zip(&solved).all(|(&g, &s)| g == 0 || g == s));
+        for backend in [Backend::DancingLinks, Backend::Sat] {
+            assert_eq!(
+                brute_force_with(&board, size, backend),
+                SolveResult::Solved(solved.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn large_boards_use_dancing_links() {
+        assert_eq!(Backend::for_size(Size::CLASSIC), Backend::Backtracking);
+        assert_eq!(
+            Backend::for_size(Size::new(4, 4).unwrap()),
+            Backend::Backtracking
+        );
+        assert_eq!(
+            Backend::for_size(Size::new(5, 5).unwrap()),
+            Backend::DancingLinks
+        );
+        let size = Size::new(5, 5).unwrap();
+        let SolveResult::Solved(solved) = brute_force(&vec![0; size.cells()], size) else {
+            panic!("empty board has solutions");
+        };
+        assert!(board_validation(&solved, size));
+    }
+
+    #[test]
+    fn backtracking_no_solution() {
+        let size = Size::CLASSIC;
+        // r1c9 can only be 9, but col 9 already holds it
+        let mut board = row_major("12345678");
+        board[size.cell_id(4, 8)] = 9;
+        assert!(board_validation(&board, size));
+        assert_eq!(
+            brute_force_with(&board, size, Backend::Backtracking),
+            SolveResult::NoSolution
+        );
+        // Broken boards are rejected before searching
+        board[size.cell_id(8, 0)] = 1;
+        assert_eq!(brute_force(&board, size), SolveResult::NoSolution);
+    }
+
+    #[test]
+    fn count_no_solutions() {
+        let size = Size::CLASSIC;
+        let mut board = row_major("12345678");
+        board[size.cell_id(4, 8)] = 9;
+        assert_eq!(count_solutions(&board, size, 2), 0);
+        assert!(!has_unique_solution(&board, size));
+    }
+
+    #[test]
+    fn count_one_solution() {
+        let size = Size::CLASSIC;
+        let board = row_major(HARD);
+        assert_eq!(count_solutions(&board, size, 2), 1);
+        assert!(has_unique_solution(&board, size));
+    }
+
+    #[test]
+    fn count_several_solutions() {
+        let size = Size::CLASSIC;
+        let empty = vec![0; size.cells()];
+        assert_eq!(count_solutions(&empty, size, 2), 2);
+        assert_eq!(count_solutions(&empty, size, 10), 10);
+        assert!(!has_unique_solution(&empty, size));
+    }
+
+    #[test]
+    fn count_wrong_length() {
+        let size = Size::CLASSIC;
+        assert_eq!(count_solutions(&[0; 82], size, 2), 0);
+        assert_eq!(count_solutions(&[0; 80], size, 2), 0);
+        assert_eq!(solutions(&[0; 16], size).next(), None);
+    }
+
+    #[test]
+    fn solutions_are_lazy() {
+        // An empty board has far too many solutions to list, taking a few must still return
+        let size = Size::CLASSIC;
+        let empty = vec![0; size.cells()];
+        let found: Vec<Vec<u8>> = solutions(&empty, size).take(5).collect();
+        assert_eq!(found.len(), 5);
+        for (i, solved) in found.iter().enumerate() {
+            assert!(board_validation(solved, size) && !solved.contains(&0));
+            assert!(!found[i + 1..].contains(solved));
+        }
+    }
+
+    #[test]
+    fn write_cell_skips_filled_cells() {
+        let size = Size::CLASSIC;
+        let mut board = vec![0; size.cells()];
+        board[0] = 5;
+        let mut state = CandidateState::new(&board, size);
+        let before = state.clone();
+        state.write_cell(0, 5);
+        state.write_cell(0, 3);
+        assert_eq!(state, before);
+        state.write_cell(1, 3);
+        assert_eq!(state.board()[1], 3);
+        assert!(!state.pencil_values()[2].contains(3));
+        assert_eq!(state.contradiction(), None);
+    }
+
+    #[test]
+    fn steps_progress_only_when_applied() {
+        let size = Size::CLASSIC;
+        let mut board = vec![0; size.cells()];
+        board[0] = 5;
+        let mut state = CandidateState::new(&board, size);
+        let step = |eliminations: Vec<(u8, u8)>, placements: Vec<(u8, u8)>| Step {
+            technique: "Test",
+            description: String::new(),
+            eliminations,
+            placements,
+        };
+        // A placement in a filled cell and an elimination of a mark already gone change nothing
+        let stale = step(vec![(1, 5)], vec![(0, 3)]);
+        assert!(!stale.makes_progress(state.pencil_values()));
+        let before = state.clone();
+        state.apply(&stale);
+        assert_eq!(state, before);
+        // A placement in an empty cell removes its other marks
+        let placement = step(Vec::new(), vec![(1, 3)]);
+        assert!(placement.makes_progress(state.pencil_values()));
+        state.apply(&placement);
+        assert_eq!(state.pencil_values()[1], Candidates::only(3));
+        assert!(!placement.makes_progress(state.pencil_values()));
+    }
+
+    #[test]
+    fn eliminate_applies_first_step() {
+        let board: Board = include_str!("../board.txt").parse().unwrap();
+        let size = board.size();
+        let pencil_values = pencil_in(&board, size);
+        let pipeline = Pipeline::default();
+        let step = pipeline.find_step(&board, &pencil_values).unwrap();
+        assert_eq!(
+            eliminate(&board, &pencil_values, &pipeline),
+            step.apply(&pencil_values)
+        );
+        let empty = Pipeline::new(Vec::new());
+        assert_eq!(eliminate(&board, &pencil_values, &empty), pencil_values);
+    }
+
+    #[test]
+    fn write_in_fills_singles() {
+        let size = Size::CLASSIC;
+        let SolveResult::Solved(solved) = brute_force(&row_major(HARD), size) else {
+            panic!("no solution");
+        };
+        // Two cells left empty, each one down to a single pencil mark
+        let mut board = solved.clone();
+        board[0] = 0;
+        board[80] = 0;
+        let pencil_values = pencil_in(&board, size);
+        assert_eq!(write_in(&board, &pencil_values, size), solved);
+    }
+
+    #[test]
+    fn solve_steps_reports_writes() {
+        let size = Size::CLASSIC;
+        let board: Vec<u8> = include_str!("../board.txt")
+            .parse::<crate::board::Board>()
+            .unwrap()
+            .into_cells();
+        let (result, steps) = solve_steps(&board, size, &Pipeline::default());
+        let SolveResult::Solved(solved) = result else {
+            panic!("not solved: {:?}", result);
+        };
+        let written = steps
+            .iter()
+            .filter(|step| matches!(step, SolveStep::Write { .. }))
+            .count();
+        assert_eq!(written, board.iter().filter(|&&v| v == 0).count());
+        for step in &steps {
+            if let SolveStep::Write { cell, value, .. } = *step {
+                assert_eq!(solved[cell], value);
+                assert!(step.describe(size).starts_with("W- r"));
             }
         }
+        assert_eq!(
+            solve_with(&board, size, &Pipeline::default()),
+            SolveResult::Solved(solved)
+        );
     }
-    new_board
 }
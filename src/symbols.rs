@@ -0,0 +1,206 @@
+//! Symbol alphabets used to read and write the values of a board
+//!
+//! Boards up to 9x9 use the digits 1-9, 16x16 boards the digits 1-9 then the letters A-G, and
+//! 25x25 boards the letters A-Y. Hex digits 0-F can be asked for with `Symbols::hex`, and are
+//! picked by `Symbols::detect` for 16x16 boards using `0` as a value. `.` and `_` are always
+//! blanks, and so is `0` unless the alphabet uses it for a value.
+
+use crate::board_ctrl::{MAX_SIDE, Size};
+
+/// Blanks accepted by every alphabet
+const BLANKS: [char; 2] = ['.', '_'];
+
+/// The symbols of an alphabet, symbol `i` stands for value `i + 1`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Symbols {
+    symbols: Vec<char>,
+}
+
+impl Symbols {
+    /// Build an alphabet from its symbols, the first one stands for value 1
+    /// `None` if there are more than `MAX_SIDE` symbols, a symbol repeats (ignoring case),
+    /// or a symbol is not an ASCII letter or digit
+    ///
+    /// Parameters:
+    /// - `symbols: &str` - one char per value, ex: `"123456789"`
+    pub fn new(symbols: &str) -> Option<Symbols> {
+        let symbols: Vec<char> = symbols.chars().collect();
+        let repeats = symbols
+            .iter()
+            .enumerate()
+            .any(|(i, a)| symbols[i + 1..].iter().any(|b| a.eq_ignore_ascii_case(b)));
+        if symbols.len() > MAX_SIDE || repeats || !symbols.iter().all(char::is_ascii_alphanumeric) {
+            return None;
+        }
+        Some(Symbols { symbols })
+    }
+
+    /// The digits 1-9
+    pub fn digits() -> Symbols {
+        Symbols {
+            symbols: "123456789".chars().collect(),
+        }
+    }
+
+    /// The digits 1-9 followed by the letters A-G, `A` stands for value 10 and `G` for value 16
+    /// Counts from 1 like `digits`, so `0` stays a blank
+    pub fn extended() -> Symbols {
+        Symbols {
+            symbols: "123456789ABCDEFG".chars().collect(),
+        }
+    }
+
+    /// The hex digits 0-F, `0` stands for value 1 and `F` for value 16
+    /// Not used by default, see `extended`, for boards written with `0` as a value
+    pub fn hex() -> Symbols {
+        Symbols {
+            symbols: "0123456789ABCDEF".chars().collect(),
+        }
+    }
+
+    /// The letters A-Y, `A` stands for value 1 and `Y` for value 25
+    pub fn letters() -> Symbols {
+        Symbols {
+            symbols: "ABCDEFGHIJKLMNOPQRSTUVWXY".chars().collect(),
+        }
+    }
+
+    /// The usual alphabet for a size of board, cut down to its side
+    /// Digits up to 9x9, `extended` up to 16x16 and letters past that
+    ///
+    /// Parameters:
+    /// - `size: Size` - size of the board
+    pub fn for_size(size: Size) -> Symbols {
+        let mut symbols = match size.side() {
+            0..=9 => Symbols::digits(),
+            10..=16 => Symbols::extended(),
+            _ => Symbols::letters(),
+        };
+        symbols.symbols.truncate(size.side());
+        symbols
+    }
+
+    /// The alphabet a board is written with, `for_size` unless a 16x16 board is written in hex
+    /// A 16x16 board is hex when it uses `0` alongside `.` or `_` blanks and has no `G`
+    ///
+    /// Parameters:
+    /// - `cells: &[char]` - one char per cell of the board
+    /// - `size: Size` - size of the board
+    pub fn detect(cells: &[char], size: Size) -> Symbols {
+        let hex = size.side() == 16
+            && cells.contains(&'0')
+            && cells.iter().any(|c| BLANKS.contains(c))
+            && !cells.iter().any(|c| c.eq_ignore_ascii_case(&'G'));
+        if hex {
+            Symbols::hex()
+        } else {
+            Symbols::for_size(size)
+        }
+    }
+
+    /// Number of symbols, the largest value the alphabet can write
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Check if the alphabet has no symbols
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Check if a char stands for an empty cell
+    ///
+    /// Parameters:
+    /// - `c: char` - char read from a board
+    pub fn is_blank(&self, c: char) -> bool {
+        BLANKS.contains(&c) || (c == '0' && !self.symbols.contains(&'0'))
+    }
+
+    /// Blank written out by `format_board_file`, `0` unless the alphabet uses it
+    pub fn blank(&self) -> char {
+        if self.symbols.contains(&'0') {
+            BLANKS[0]
+        } else {
+            '0'
+        }
+    }
+
+    /// Value of a char, 0 for a blank and `None` if the char is neither a symbol nor a blank
+    /// Letters match either case
+    ///
+    /// Parameters:
+    /// - `c: char` - char read from a board
+    pub fn value(&self, c: char) -> Option<u8> {
+        if self.is_blank(c) {
+            return Some(0);
+        }
+        self.symbols
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case(&c))
+            .map(|i| i as u8 + 1)
+    }
+
+    /// Symbol of a value, `None` for 0 (empty) and values past the end of the alphabet
+    ///
+    /// Parameters:
+    /// - `value: u8` - value of a cell
+    pub fn symbol(&self, value: u8) -> Option<char> {
+        let i = (value as usize).checked_sub(1)?;
+        self.symbols.get(i).copied()
+    }
+}
+
+impl Default for Symbols {
+    /// The digits 1-9 of a classic board
+    fn default() -> Self {
+        Symbols::digits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sixteen_counts_from_one() {
+        let symbols = Symbols::for_size(Size::new(4, 4).unwrap());
+        assert_eq!(symbols, Symbols::extended());
+        assert_eq!(symbols.value('1'), Some(1));
+        assert_eq!(symbols.value('g'), Some(16));
+        assert_eq!(symbols.value('0'), Some(0));
+        assert_eq!(symbols.symbol(10), Some('A'));
+        assert_eq!(symbols.blank(), '0');
+    }
+
+    #[test]
+    fn cut_to_side() {
+        let symbols = Symbols::for_size(Size::new(4, 3).unwrap());
+        assert_eq!(symbols.len(), 12);
+        assert_eq!(symbols.symbol(12), Some('C'));
+        assert_eq!(symbols.value('D'), None);
+    }
+
+    #[test]
+    fn hex_uses_zero() {
+        let symbols = Symbols::hex();
+        assert_eq!(symbols.value('0'), Some(1));
+        assert_eq!(symbols.value('f'), Some(16));
+        assert_eq!(symbols.value('.'), Some(0));
+        assert_eq!(symbols.blank(), '.');
+    }
+
+    #[test]
+    fn detect_hex() {
+        let size = Size::new(4, 4).unwrap();
+        let hex: Vec<char> = "0F..".chars().collect();
+        assert_eq!(Symbols::detect(&hex, size), Symbols::hex());
+        // `0` blanks, or a `G`, are the usual 1-G
+        let zero_blanks: Vec<char> = "1F00".chars().collect();
+        assert_eq!(Symbols::detect(&zero_blanks, size), Symbols::extended());
+        let with_g: Vec<char> = "0G..".chars().collect();
+        assert_eq!(Symbols::detect(&with_g, size), Symbols::extended());
+        // Only 16x16 boards are read in hex
+        let classic: Vec<char> = "01..".chars().collect();
+        assert_eq!(Symbols::detect(&classic, Size::CLASSIC), Symbols::digits());
+    }
+}
@@ -0,0 +1,366 @@
+//! Human style solving techniques
+//!
+//! Each technique looks at the board and its pencil marks and reports a [`Step`]: pencil marks to
+//! remove and values to write in. Techniques are run by a [`Pipeline`] in the order they were added,
+//! so the cheapest techniques should come first. See [`Technique`] for the boards they handle.
+
+pub mod als;
+pub mod chains;
+pub mod coloring;
+pub mod fish;
+pub mod forcing;
+pub mod intersections;
+pub mod single_digit;
+pub mod subsets;
+pub mod uniqueness;
+pub mod wings;
+
+use crate::board_ctrl::{Unit, cell_name, cells_see, get_all_units};
+use crate::candidates::Candidates;
+use als::{AlsXyWing, AlsXz, DeathBlossom, SueDeCoq};
+use chains::{Chain, ChainKind};
+use coloring::{MultiColoring, SimpleColoring};
+use fish::Fish;
+use forcing::{DEFAULT_FORCING_DEPTH, ForcingChain, ForcingKind, Nishio};
+use intersections::{BoxLineReduction, Pointing};
+use single_digit::{EmptyRectangle, Skyscraper, TurbotFish, TwoStringKite};
+use subsets::{HiddenSubset, NakedSubset};
+use uniqueness::{AvoidableRectangle, BugPlusOne, UniqueRectangle};
+use wings::{WWing, XYWing, XYZWing};
+
+/// A single deduction found by a technique
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// Name of the technique that found this step
+    pub technique: &'static str,
+    /// Explanation of the deduction
+    pub description: String,
+    /// Pencil marks to remove as `(cell idx, value)`
+    pub eliminations: Vec<(u8, u8)>,
+    /// Values to write in as `(cell idx, value)`
+    pub placements: Vec<(u8, u8)>,
+}
+
+impl Step {
+    /// Pencil marks the step removes from the given pencil marks, as `(cell idx, value)`
+    /// A placement removes every other mark of its cell, so placing a value in a filled cell, or in
+    /// a cell already down to that value, removes nothing
+    ///
+    /// Parameters:
+    /// - `pencil_values: &[Candidates]` - reference to current pencil marks
+    pub fn removals(&self, pencil_values: &[Candidates]) -> Vec<(u8, u8)> {
+        let mut removals: Vec<(u8, u8)> = self
+            .eliminations
+            .iter()
+            .copied()
+            .filter(|&(cell, value)| pencil_values[cell as usize].contains(value))
+            .collect();
+        for &(cell, value) in self.placements.iter() {
+            let others = pencil_values[cell as usize] - Candidates::only(value);
+            removals.extend(others.iter().map(|other| (cell, other)));
+        }
+        removals.sort_unstable();
+        removals.dedup();
+        removals
+    }
+
+    /// Check if the step would change the given pencil marks, see `removals`
+    ///
+    /// Parameters:
+    /// - `pencil_values: &[Candidates]` - reference to current pencil marks
+    pub fn makes_progress(&self, pencil_values: &[Candidates]) -> bool {
+        !self.removals(pencil_values).is_empty()
+    }
+
+    /// Apply the step to a copy of the pencil marks, see `removals`
+    /// Placements leave the cell with a single pencil mark so that `write_in` will fill it
+    ///
+    /// Parameters:
+    /// - `pencil_values: &[Candidates]` - reference to current pencil marks
+    pub fn apply(&self, pencil_values: &[Candidates]) -> Vec<Candidates> {
+        let mut new_pencil: Vec<Candidates> = pencil_values.to_vec();
+        for (cell, value) in self.removals(pencil_values) {
+            new_pencil[cell as usize].remove(value);
+        }
+        new_pencil
+    }
+}
+
+/// A named solving technique
+/// Techniques only handle the classic 9x9 board: cells are `u8` ids into the 81 cells and units
+/// come from `get_all_units`. `solve_with` only runs a pipeline on 9x9 boards, so `find` is free
+/// to assume that shape, and other sizes go straight from singles to brute force
+pub trait Technique {
+    /// Name used when reporting steps
+    fn name(&self) -> &'static str;
+
+    /// Search for a step that removes pencil marks or writes in values
+    ///
+    /// Parameters:
+    /// - `board: &[u8]` - reference to current board
+    /// - `pencil_values: &[Candidates]` - reference to current pencil marks
+    fn find(&self, board: &[u8], pencil_values: &[Candidates]) -> Option<Step>;
+}
+
+/// An ordered list of techniques to try, see `Technique` for the boards it runs on
+pub struct Pipeline {
+    techniques: Vec<Box<dyn Technique>>,
+}
+
+impl Pipeline {
+    /// Create a pipeline that runs the techniques in the given order
+    pub fn new(techniques: Vec<Box<dyn Technique>>) -> Self {
+        Pipeline { techniques }
+    }
+
+    /// Every available technique plus those that assume the puzzle has exactly one solution
+    /// Only use this for proper puzzles, the extra deductions are wrong when a puzzle has several solutions
+    ///
+    /// Parameters:
+    /// - `givens: &[bool]` - the clues of the puzzle, from `get_givens`
+    pub fn with_uniqueness(givens: &[bool]) -> Self {
+        Pipeline::new(standard_techniques(Some(givens)))
+    }
+
+    /// Add a technique to the end of the pipeline
+    pub fn push(&mut self, technique: Box<dyn Technique>) {
+        self.techniques.push(technique);
+    }
+
+    /// Names of the techniques in the order they are tried
+    pub fn names(&self) -> Vec<&'static str> {
+        self.techniques.iter().map(|t| t.name()).collect()
+    }
+
+    /// Find the first step that makes progress, trying techniques in order
+    ///
+    /// Parameters:
+    /// - `board: &[u8]` - reference to current board
+    /// - `pencil_values: &[Candidates]` - reference to current pencil marks
+    pub fn find_step(&self, board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        self.techniques
+            .iter()
+            .filter_map(|t| t.find(board, pencil_values))
+            .find(|step| step.makes_progress(pencil_values))
+    }
+}
+
+impl Default for Pipeline {
+    /// Every available technique, cheapest first
+    fn default() -> Self {
+        Pipeline::new(standard_techniques(None))
+    }
+}
+
+/// Every available technique, cheapest first
+/// Uniqueness techniques are only included when the clues are given
+fn standard_techniques(givens: Option<&[bool]>) -> Vec<Box<dyn Technique>> {
+    let mut techniques: Vec<Box<dyn Technique>> = vec![
+        Box::new(Pointing),
+        Box::new(BoxLineReduction),
+        Box::new(NakedSubset { size: 2 }),
+        Box::new(HiddenSubset { size: 2 }),
+        Box::new(Fish {
+            size: 2,
+            finned: false,
+        }),
+        Box::new(NakedSubset { size: 3 }),
+        Box::new(HiddenSubset { size: 3 }),
+        Box::new(Fish {
+            size: 3,
+            finned: false,
+        }),
+        Box::new(Skyscraper),
+        Box::new(TwoStringKite),
+        Box::new(TurbotFish),
+        Box::new(EmptyRectangle),
+        Box::new(SimpleColoring),
+        Box::new(XYWing),
+        Box::new(XYZWing),
+        Box::new(WWing),
+        Box::new(NakedSubset { size: 4 }),
+        Box::new(HiddenSubset { size: 4 }),
+        Box::new(Fish {
+            size: 4,
+            finned: false,
+        }),
+        Box::new(MultiColoring),
+        Box::new(Fish {
+            size: 2,
+            finned: true,
+        }),
+        Box::new(Fish {
+            size: 3,
+            finned: true,
+        }),
+        Box::new(Fish {
+            size: 4,
+            finned: true,
+        }),
+    ];
+    if let Some(givens) = givens {
+        techniques.push(Box::new(UniqueRectangle));
+        techniques.push(Box::new(BugPlusOne));
+        techniques.push(Box::new(AvoidableRectangle {
+            givens: givens.to_vec(),
+        }));
+    }
+    techniques.push(Box::new(Chain {
+        kind: ChainKind::XChain,
+    }));
+    techniques.push(Box::new(Chain {
+        kind: ChainKind::XYChain,
+    }));
+    techniques.push(Box::new(SueDeCoq));
+    techniques.push(Box::new(AlsXz));
+    techniques.push(Box::new(AlsXyWing));
+    techniques.push(Box::new(DeathBlossom));
+    techniques.push(Box::new(Chain {
+        kind: ChainKind::Aic,
+    }));
+    techniques.push(Box::new(Nishio {
+        depth: DEFAULT_FORCING_DEPTH,
+    }));
+    techniques.push(Box::new(ForcingChain {
+        kind: ForcingKind::Cell,
+        depth: DEFAULT_FORCING_DEPTH,
+    }));
+    techniques.push(Box::new(ForcingChain {
+        kind: ForcingKind::Unit,
+        depth: DEFAULT_FORCING_DEPTH,
+    }));
+    techniques.push(Box::new(ForcingChain {
+        kind: ForcingKind::Digit,
+        depth: DEFAULT_FORCING_DEPTH,
+    }));
+    techniques
+}
+
+/// Format a list of cells in row/col notation, ex: `r1c1,r1c5`
+///
+/// Parameters:
+/// - `cells: &[u8]` - cell indices
+pub fn format_cells(cells: &[u8]) -> String {
+    let names: Vec<String> = cells.iter().map(|&c| cell_name(c)).collect();
+    names.join(",")
+}
+
+/// Remove a value from every cell that sees all of the given cells
+/// The usual conclusion when one of `cells` must hold the value
+///
+/// Parameters:
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+/// - `value: u8` - value to remove
+/// - `cells: &[u8]` - cells that the eliminated cells must all see
+pub fn eliminate_seen(pencil_values: &[Candidates], value: u8, cells: &[u8]) -> Vec<(u8, u8)> {
+    (0..81u8)
+        .filter(|&c| pencil_values[c as usize].contains(value))
+        .filter(|&c| cells.iter().all(|&other| cells_see(c, other)))
+        .map(|c| (c, value))
+        .collect()
+}
+
+/// Two cells that are the only places for a value in a unit (a strong link)
+/// One of the two cells must hold the value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConjugatePair {
+    pub value: u8,
+    pub cells: [u8; 2],
+    pub unit: &'static Unit,
+}
+
+/// Find every conjugate pair of a value, in unit order (boxes, rows, cols)
+/// A pair of cells sharing a box and a line is reported once for each unit
+///
+/// Parameters:
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+/// - `value: u8` - value to link
+pub fn find_conjugate_pairs(pencil_values: &[Candidates], value: u8) -> Vec<ConjugatePair> {
+    get_all_units()
+        .iter()
+        .filter_map(|unit| {
+            let marked: Vec<u8> = unit
+                .cells
+                .iter()
+                .copied()
+                .filter(|&c| pencil_values[c as usize].contains(value))
+                .collect();
+            if marked.len() != 2 {
+                return None;
+            }
+            Some(ConjugatePair {
+                value,
+                cells: [marked[0], marked[1]],
+                unit,
+            })
+        })
+        .collect()
+}
+
+/// Every way of choosing `size` items, keeping their order
+///
+/// Parameters:
+/// - `items: &[T]` - items to choose from
+/// - `size: usize` - number of items in each combination
+pub fn combinations<T: Copy>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 {
+        return vec![Vec::new()];
+    }
+    if items.len() < size {
+        return Vec::new();
+    }
+    let mut combos = Vec::new();
+    for (i, &first) in items.iter().enumerate() {
+        for mut rest in combinations(&items[i + 1..], size - 1) {
+            rest.insert(0, first);
+            combos.push(rest);
+        }
+    }
+    combos
+}
+
+/// Small pencil mark grids for the technique tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::board_ctrl::get_cell_id;
+    use crate::candidates::Candidates;
+
+    /// Pencil marks holding only the given `(row, col, value)` marks
+    pub fn marks(cells: &[(u8, u8, u8)]) -> Vec<Candidates> {
+        let mut pencil_values = vec![Candidates::NONE; 81];
+        for &(row, col, value) in cells {
+            pencil_values[get_cell_id(row, col) as usize].insert(value);
+        }
+        pencil_values
+    }
+
+    /// Pencil marks holding only the given `(row, col, digits)` cells, ex: `(0, 0, 129)` is {1,2,9}
+    pub fn cells(cells: &[(u8, u8, u32)]) -> Vec<Candidates> {
+        let mut pencil_values = vec![Candidates::NONE; 81];
+        for &(row, col, digits) in cells {
+            pencil_values[get_cell_id(row, col) as usize] =
+                digits.to_string().chars().map(|d| d as u8 - b'0').collect();
+        }
+        pencil_values
+    }
+
+    /// A board holding only the given `(row, col, value)` values
+    pub fn board(values: &[(u8, u8, u8)]) -> Vec<u8> {
+        let mut board = vec![0; 81];
+        for &(row, col, value) in values {
+            board[get_cell_id(row, col) as usize] = value;
+        }
+        board
+    }
+
+    /// `(cell idx, value)` of a row and col, as in a step's eliminations and placements
+    pub fn at(row: u8, col: u8, value: u8) -> (u8, u8) {
+        (get_cell_id(row, col), value)
+    }
+
+    /// Eliminations or placements in order, to compare against a list of `at`
+    pub fn sorted(mut found: Vec<(u8, u8)>) -> Vec<(u8, u8)> {
+        found.sort_unstable();
+        found
+    }
+}
@@ -0,0 +1,623 @@
+//! Almost Locked Set (ALS) techniques
+//!
+//! An ALS is `n` empty cells of one unit holding `n + 1` values between them. Removing any one value
+//! locks the rest into the set. Two sets are joined by a restricted common value `x` when every
+//! cell with `x` in one sees every cell with `x` in the other: at most one of them can hold `x`.
+
+use std::collections::HashSet;
+
+use crate::board_ctrl::{Unit, UnitKind, cells_see, get_all_units, get_unit};
+use crate::candidates::Candidates;
+use crate::techniques::{Step, Technique, combinations, format_cells};
+
+/// Cells as a bit set, bit `n` is cell idx `n`
+type CellSet = u128;
+
+/// Turn a list of cells into a bit set
+fn cell_set(cells: &[u8]) -> CellSet {
+    cells.iter().fold(0, |acc, &c| acc | (1 << c))
+}
+
+/// Turn a bit set back into a list of cells
+fn set_cells(set: CellSet) -> Vec<u8> {
+    (0..81u8).filter(|&c| set & (1 << c) != 0).collect()
+}
+
+/// Bit set of the peers of every cell
+fn peer_sets() -> Vec<CellSet> {
+    (0..81u8)
+        .map(|a| {
+            (0..81u8)
+                .filter(|&b| cells_see(a, b))
+                .fold(0, |acc, b| acc | (1 << b))
+        })
+        .collect()
+}
+
+/// An Almost Locked Set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Als {
+    pub cells: Vec<u8>,
+    pub mark: Candidates,
+    pub unit: Unit,
+}
+
+/// Find every ALS in the boxes, rows and cols of the board
+/// A set lying in a box and a line is only reported for the first unit it is found in
+///
+/// Parameters:
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+pub fn find_als(pencil_values: &[Candidates]) -> Vec<Als> {
+    let mut found: Vec<Als> = Vec::new();
+    let mut seen: HashSet<CellSet> = HashSet::new();
+    for unit in get_all_units() {
+        let empty: Vec<u8> = unit
+            .cells
+            .iter()
+            .copied()
+            .filter(|&c| !pencil_values[c as usize].is_empty())
+            .collect();
+        for size in 1..empty.len() {
+            for cells in combinations(&empty, size) {
+                let mark = cells
+                    .iter()
+                    .fold(Candidates::NONE, |acc, &c| acc | pencil_values[c as usize]);
+                if mark.len() != size + 1 || !seen.insert(cell_set(&cells)) {
+                    continue;
+                }
+                found.push(Als {
+                    cells,
+                    mark,
+                    unit: unit.clone(),
+                });
+            }
+        }
+    }
+    found
+}
+
+/// An ALS with bit sets precomputed for quick searching
+struct AlsInfo {
+    als: Als,
+    cells: CellSet,
+    /// Cells of the set holding each value
+    holding: [CellSet; 9],
+    /// Cells seeing every cell of the set holding each value
+    seeing: [CellSet; 9],
+}
+
+impl AlsInfo {
+    fn build(pencil_values: &[Candidates], peers: &[CellSet]) -> Vec<AlsInfo> {
+        find_als(pencil_values)
+            .into_iter()
+            .map(|als| {
+                let holding: [CellSet; 9] = std::array::from_fn(|i| {
+                    als.cells
+                        .iter()
+                        .filter(|&&c| pencil_values[c as usize].contains(i as u8 + 1))
+                        .fold(0, |acc, &c| acc | (1 << c))
+                });
+                let seeing: [CellSet; 9] = std::array::from_fn(|i| {
+                    set_cells(holding[i])
+                        .iter()
+                        .fold(CellSet::MAX, |acc, &c| acc & peers[c as usize])
+                });
+                AlsInfo {
+                    cells: cell_set(&als.cells),
+                    als,
+                    holding,
+                    seeing,
+                }
+            })
+            .collect()
+    }
+
+    /// Restricted common values shared with another, non overlapping, set
+    fn restricted_common(&self, other: &AlsInfo) -> Candidates {
+        if self.cells & other.cells != 0 {
+            return Candidates::NONE;
+        }
+        (self.als.mark & other.als.mark)
+            .iter()
+            .filter(|&v| {
+                let i = (v - 1) as usize;
+                self.holding[i] & !other.seeing[i] == 0
+            })
+            .collect()
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{}{} ({})",
+            format_cells(&self.als.cells),
+            self.als.mark,
+            self.als.unit
+        )
+    }
+}
+
+/// Remove a value from every cell in `targets` still marked for it
+fn marked_eliminations(pencil_values: &[Candidates], value: u8, targets: CellSet) -> Vec<(u8, u8)> {
+    set_cells(targets)
+        .into_iter()
+        .filter(|&c| pencil_values[c as usize].contains(value))
+        .map(|c| (c, value))
+        .collect()
+}
+
+/// Add eliminations to a list, skipping repeats
+fn merge(eliminations: &mut Vec<(u8, u8)>, more: Vec<(u8, u8)>) {
+    for elim in more {
+        if !eliminations.contains(&elim) {
+            eliminations.push(elim);
+        }
+    }
+}
+
+/// ALS-XZ
+/// Two sets joined by a restricted common value `x`: one of them loses `x` and becomes locked,
+/// so any other shared value `z` is in one of them. With two restricted common values both sets
+/// are locked and every value they hold is removed from cells seeing all its holders
+pub struct AlsXz;
+
+impl Technique for AlsXz {
+    fn name(&self) -> &'static str {
+        "ALS-XZ"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let peers = peer_sets();
+        let sets = AlsInfo::build(pencil_values, &peers);
+        for (i, a) in sets.iter().enumerate() {
+            for b in sets[i + 1..].iter() {
+                let rcc = a.restricted_common(b);
+                if rcc.is_empty() {
+                    continue;
+                }
+                let outside = !(a.cells | b.cells);
+                let mut eliminations = Vec::new();
+                if rcc.len() == 1 {
+                    for z in (a.als.mark & b.als.mark) - rcc {
+                        let zi = (z - 1) as usize;
+                        let targets = a.seeing[zi] & b.seeing[zi] & outside;
+                        merge(
+                            &mut eliminations,
+                            marked_eliminations(pencil_values, z, targets),
+                        );
+                    }
+                } else {
+                    for x in rcc {
+                        let xi = (x - 1) as usize;
+                        let targets = a.seeing[xi] & b.seeing[xi] & outside;
+                        merge(
+                            &mut eliminations,
+                            marked_eliminations(pencil_values, x, targets),
+                        );
+                    }
+                    for set in [a, b] {
+                        for z in set.als.mark - rcc {
+                            let targets = set.seeing[(z - 1) as usize] & !set.cells;
+                            merge(
+                                &mut eliminations,
+                                marked_eliminations(pencil_values, z, targets),
+                            );
+                        }
+                    }
+                }
+                if !eliminations.is_empty() {
+                    let technique = if rcc.len() == 1 {
+                        self.name()
+                    } else {
+                        "Doubly Linked ALS-XZ"
+                    };
+                    return Some(Step {
+                        technique,
+                        description: format!(
+                            "A={} and B={} restricted common {}",
+                            a.describe(),
+                            b.describe(),
+                            rcc
+                        ),
+                        eliminations,
+                        placements: Vec::new(),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// ALS-XY-Wing
+/// A pivot set C joined to set A by restricted common `x` and to set B by a different `y`.
+/// A or B loses nothing to C only if C is short two values, so one of A or B holds any shared `z`
+pub struct AlsXyWing;
+
+impl Technique for AlsXyWing {
+    fn name(&self) -> &'static str {
+        "ALS-XY-Wing"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let peers = peer_sets();
+        let sets = AlsInfo::build(pencil_values, &peers);
+        for pivot in sets.iter() {
+            let linked: Vec<(&AlsInfo, Candidates)> = sets
+                .iter()
+                .map(|other| (other, pivot.restricted_common(other)))
+                .filter(|&(_, rcc)| !rcc.is_empty())
+                .collect();
+            for (i, &(a, rcc_a)) in linked.iter().enumerate() {
+                for &(b, rcc_b) in linked[i + 1..].iter() {
+                    if a.cells & b.cells != 0 {
+                        continue;
+                    }
+                    for x in rcc_a {
+                        for y in rcc_b {
+                            if x == y {
+                                continue;
+                            }
+                            let xy = Candidates::only(x) | Candidates::only(y);
+                            let mut eliminations = Vec::new();
+                            for z in (a.als.mark & b.als.mark) - xy {
+                                let zi = (z - 1) as usize;
+                                let targets = a.seeing[zi] & b.seeing[zi] & !(a.cells | b.cells);
+                                merge(
+                                    &mut eliminations,
+                                    marked_eliminations(pencil_values, z, targets),
+                                );
+                            }
+                            if !eliminations.is_empty() {
+                                return Some(Step {
+                                    technique: self.name(),
+                                    description: format!(
+                                        "pivot C={} joined to A={} by {} and to B={} by {}",
+                                        pivot.describe(),
+                                        a.describe(),
+                                        x,
+                                        b.describe(),
+                                        y
+                                    ),
+                                    eliminations,
+                                    placements: Vec::new(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Death Blossom
+/// A stem cell with one petal set per pencil mark, each petal holding that mark only in cells
+/// seeing the stem. Whichever value the stem takes locks its petal, so one of the petals holds any
+/// value `z` shared by all petals (and not in the stem)
+pub struct DeathBlossom;
+
+/// Largest stem searched, in pencil marks
+const MAX_STEM_MARKS: usize = 3;
+
+impl DeathBlossom {
+    /// Pick one petal per remaining stem value, keeping the petals apart
+    fn grow(
+        petals_for: &[(u8, Vec<&AlsInfo>)],
+        chosen: &mut Vec<usize>,
+        used: CellSet,
+        shared: Candidates,
+        pencil_values: &[Candidates],
+    ) -> Option<Vec<(u8, u8)>> {
+        if shared.is_empty() {
+            return None;
+        }
+        let depth = chosen.len();
+        if depth == petals_for.len() {
+            let mut eliminations = Vec::new();
+            for z in shared {
+                let zi = (z - 1) as usize;
+                let targets = chosen
+                    .iter()
+                    .enumerate()
+                    .fold(!used, |acc, (i, &p)| acc & petals_for[i].1[p].seeing[zi]);
+                merge(
+                    &mut eliminations,
+                    marked_eliminations(pencil_values, z, targets),
+                );
+            }
+            return if eliminations.is_empty() {
+                None
+            } else {
+                Some(eliminations)
+            };
+        }
+        for (p, petal) in petals_for[depth].1.iter().enumerate() {
+            if petal.cells & used != 0 {
+                continue;
+            }
+            chosen.push(p);
+            let found = DeathBlossom::grow(
+                petals_for,
+                chosen,
+                used | petal.cells,
+                shared & petal.als.mark,
+                pencil_values,
+            );
+            if found.is_some() {
+                return found;
+            }
+            chosen.pop();
+        }
+        None
+    }
+}
+
+impl Technique for DeathBlossom {
+    fn name(&self) -> &'static str {
+        "Death Blossom"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let peers = peer_sets();
+        let sets = AlsInfo::build(pencil_values, &peers);
+        for stem in 0..81u8 {
+            let stem_mark = pencil_values[stem as usize];
+            if stem_mark.len() < 2 || stem_mark.len() > MAX_STEM_MARKS {
+                continue;
+            }
+            let stem_peers = peers[stem as usize];
+            let petals_for: Vec<(u8, Vec<&AlsInfo>)> = stem_mark
+                .iter()
+                .map(|v| {
+                    let vi = (v - 1) as usize;
+                    let petals = sets
+                        .iter()
+                        .filter(|s| s.cells & (1 << stem) == 0)
+                        .filter(|s| s.holding[vi] != 0 && s.holding[vi] & !stem_peers == 0)
+                        .filter(|s| !(s.als.mark - stem_mark).is_empty())
+                        .collect();
+                    (v, petals)
+                })
+                .collect();
+            let mut chosen = Vec::new();
+            let used: CellSet = 1 << stem;
+            if let Some(eliminations) = DeathBlossom::grow(
+                &petals_for,
+                &mut chosen,
+                used,
+                Candidates::all(9) - stem_mark,
+                pencil_values,
+            ) {
+                let petals: Vec<String> = chosen
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &p)| {
+                        format!("{}: {}", petals_for[i].0, petals_for[i].1[p].describe())
+                    })
+                    .collect();
+                return Some(Step {
+                    technique: self.name(),
+                    description: format!(
+                        "stem {}{} with petals {}",
+                        format_cells(&[stem]),
+                        stem_mark,
+                        petals.join("; ")
+                    ),
+                    eliminations,
+                    placements: Vec::new(),
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Sue de Coq
+/// Two or three cells where a box crosses a line, holding at least two more values than cells.
+/// Joined with cells from the rest of the line and the rest of the box that share no values,
+/// the cells hold exactly as many values as there are cells, so each value is placed once:
+/// line values stay in the line, box values stay in the box, the rest stay in the crossing
+pub struct SueDeCoq;
+
+/// Largest group of cells taken from the rest of the line or box
+const MAX_SUE_DE_COQ_CELLS: usize = 3;
+
+impl SueDeCoq {
+    /// Check one choice of crossing cells with cells from the rest of the line and box
+    fn check(
+        pencil_values: &[Candidates],
+        c_box: &Unit,
+        line: &Unit,
+        core: &[u8],
+        line_cells: &[u8],
+        box_cells: &[u8],
+    ) -> Option<Step> {
+        let mark_of = |cells: &[u8]| {
+            cells
+                .iter()
+                .fold(Candidates::NONE, |acc, &c| acc | pencil_values[c as usize])
+        };
+        let (core_mark, line_mark, box_mark) =
+            (mark_of(core), mark_of(line_cells), mark_of(box_cells));
+        let all = core_mark | line_mark | box_mark;
+        if !(line_mark & box_mark).is_empty()
+            || all.len() != core.len() + line_cells.len() + box_cells.len()
+        {
+            return None;
+        }
+        let mut eliminations = Vec::new();
+        for (unit, keep, values) in [
+            (line, line_cells, all - box_mark),
+            (c_box, box_cells, all - line_mark),
+        ] {
+            for &c in unit.cells.iter() {
+                if core.contains(&c) || keep.contains(&c) {
+                    continue;
+                }
+                let found = (pencil_values[c as usize] & values)
+                    .iter()
+                    .map(|v| (c, v))
+                    .collect();
+                merge(&mut eliminations, found);
+            }
+        }
+        if eliminations.is_empty() {
+            return None;
+        }
+        Some(Step {
+            technique: "Sue de Coq",
+            description: format!(
+                "{}{} where {} crosses {}, with {}{} and {}{}",
+                format_cells(core),
+                core_mark,
+                c_box,
+                line,
+                format_cells(line_cells),
+                line_mark,
+                format_cells(box_cells),
+                box_mark
+            ),
+            eliminations,
+            placements: Vec::new(),
+        })
+    }
+}
+
+impl Technique for SueDeCoq {
+    fn name(&self) -> &'static str {
+        "Sue de Coq"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let empty_of = |cells: &[u8], outside: &[u8], inside: bool| -> Vec<u8> {
+            cells
+                .iter()
+                .copied()
+                .filter(|c| outside.contains(c) == inside && !pencil_values[*c as usize].is_empty())
+                .collect()
+        };
+        for box_id in 0..9 {
+            let c_box = get_unit(UnitKind::Box, box_id);
+            for kind in [UnitKind::Row, UnitKind::Col] {
+                for line_id in 0..9 {
+                    let line = get_unit(kind, line_id);
+                    let crossing = empty_of(c_box.cells, line.cells, true);
+                    let line_rest = empty_of(line.cells, c_box.cells, false);
+                    let box_rest = empty_of(c_box.cells, line.cells, false);
+                    let line_groups: Vec<Vec<u8>> = (1..=MAX_SUE_DE_COQ_CELLS)
+                        .flat_map(|size| combinations(&line_rest, size))
+                        .collect();
+                    let box_groups: Vec<Vec<u8>> = (1..=MAX_SUE_DE_COQ_CELLS)
+                        .flat_map(|size| combinations(&box_rest, size))
+                        .collect();
+                    for size in 2..=crossing.len() {
+                        for core in combinations(&crossing, size) {
+                            let core_mark = core
+                                .iter()
+                                .fold(Candidates::NONE, |acc, &c| acc | pencil_values[c as usize]);
+                            if core_mark.len() < size + 2 {
+                                continue;
+                            }
+                            for line_cells in line_groups.iter() {
+                                for box_cells in box_groups.iter() {
+                                    let step = SueDeCoq::check(
+                                        pencil_values,
+                                        c_box,
+                                        line,
+                                        &core,
+                                        line_cells,
+                                        box_cells,
+                                    );
+                                    if step.is_some() {
+                                        return step;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_ctrl::get_cell_id;
+    use crate::techniques::testing::{at, cells, sorted};
+
+    #[test]
+    fn als_in_box_and_line_found_once() {
+        // r1c1 and r1c2 share box 1 and row 1, both of which have another empty cell
+        let pencil_values = cells(&[(0, 0, 12), (0, 1, 23), (2, 2, 56), (0, 8, 78)]);
+        let pair = vec![get_cell_id(0, 0), get_cell_id(0, 1)];
+        let found: Vec<Als> = find_als(&pencil_values)
+            .into_iter()
+            .filter(|als| als.cells == pair)
+            .collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].unit, *get_unit(UnitKind::Box, 0));
+        assert_eq!(found[0].mark, [1, 2, 3].into_iter().collect());
+    }
+
+    #[test]
+    fn als_xz() {
+        // A=r1c1 {1,2} and B=r1c5,r4c5 {1,2,3} see each other on 1, so one of them holds 2
+        let pencil_values = cells(&[(0, 0, 12), (0, 4, 13), (3, 4, 23), (6, 4, 67), (3, 0, 25)]);
+        let step = AlsXz.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "ALS-XZ");
+        assert_eq!(step.eliminations, vec![at(3, 0, 2)]);
+    }
+
+    #[test]
+    fn doubly_linked_als_xz() {
+        // A=r1c1 {1,2} and B=r1c5,r1c6 {1,2,3} see each other on both 1 and 2
+        let pencil_values = cells(&[(0, 0, 12), (0, 4, 23), (0, 5, 13), (0, 8, 19), (1, 4, 39)]);
+        let step = AlsXz.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Doubly Linked ALS-XZ");
+        assert_eq!(sorted(step.eliminations), vec![at(1, 4, 3), at(0, 8, 1)]);
+    }
+
+    #[test]
+    fn als_xy_wing() {
+        // C=r1c1 {1,2} links to A=r1c5 {1,3} by 1 and to B=r5c1 {2,3} by 2, A or B holds 3
+        let pencil_values = cells(&[(0, 0, 12), (0, 4, 13), (4, 0, 23), (4, 4, 39)]);
+        let step = AlsXyWing.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "ALS-XY-Wing");
+        assert_eq!(step.eliminations, vec![at(4, 4, 3)]);
+    }
+
+    #[test]
+    fn death_blossom() {
+        // Stem r1c1 {1,2}, petal r1c5 {1,3} for 1 and petal r5c1,r6c1 {2,3,5} for 2
+        let pencil_values = cells(&[(0, 0, 12), (0, 4, 13), (4, 0, 25), (5, 0, 35), (5, 4, 37)]);
+        let step = DeathBlossom.find(&[0; 81], &pencil_values).unwrap();
+        assert!(step.description.starts_with("stem r1c1{1,2}"));
+        assert_eq!(step.eliminations, vec![at(5, 4, 3)]);
+    }
+
+    #[test]
+    fn sue_de_coq() {
+        // r1c1,r1c2 {1,2,3,4} with r1c6 {1,2} from row 1 and r2c1 {3,4} from box 1
+        let pencil_values = cells(&[
+            (0, 0, 13),
+            (0, 1, 24),
+            (0, 5, 12),
+            (1, 0, 34),
+            (0, 7, 19),
+            (2, 2, 38),
+        ]);
+        let step = SueDeCoq.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(sorted(step.eliminations), vec![at(2, 2, 3), at(0, 7, 1)]);
+    }
+
+    #[test]
+    fn sue_de_coq_needs_apart_line_and_box() {
+        // r2c1 {2,4} shares 2 with r1c6 {1,2}, so the values aren't split between line and box
+        let pencil_values = cells(&[(0, 0, 13), (0, 1, 24), (0, 5, 12), (1, 0, 24), (0, 7, 19)]);
+        assert_eq!(SueDeCoq.find(&[0; 81], &pencil_values), None);
+    }
+}
@@ -0,0 +1,545 @@
+//! Alternating Inference Chains (AIC) over pencil marks, with grouped nodes
+//!
+//! A node is a value in one cell, or a value in a group of cells where a box crosses a line.
+//! Strong links join nodes where at least one must be true, weak links join nodes where at most
+//! one can be true. A chain starting and ending on strong links proves one of its two ends true.
+//! Chains are written in Eureka notation, ex: `(4)r1c2=(4)r1c5-(4)r3c5=(4)r3c1`.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::board_ctrl::{
+    UnitKind, cell_name, cells_see, get_all_units, get_cell_col, get_cell_row, get_unit,
+};
+use crate::candidates::Candidates;
+use crate::techniques::{Step, Technique, eliminate_seen};
+
+/// Longest chain searched, in nodes
+const MAX_CHAIN_NODES: usize = 16;
+
+/// A value in one cell or in a group of cells sharing a box and a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainNode {
+    pub value: u8,
+    pub cells: Vec<u8>,
+}
+
+impl ChainNode {
+    fn overlaps(&self, other: &ChainNode) -> bool {
+        self.value == other.value && self.cells.iter().any(|c| other.cells.contains(c))
+    }
+}
+
+impl fmt::Display for ChainNode {
+    /// Eureka node, ex: `(4)r1c2` or the group `(4)r1c23`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rows: Vec<u8> = self.cells.iter().map(|&c| get_cell_row(c) + 1).collect();
+        let mut cols: Vec<u8> = self.cells.iter().map(|&c| get_cell_col(c) + 1).collect();
+        rows.sort_unstable();
+        rows.dedup();
+        cols.sort_unstable();
+        cols.dedup();
+        let rows: String = rows.iter().map(|r| r.to_string()).collect();
+        let cols: String = cols.iter().map(|c| c.to_string()).collect();
+        write!(f, "({})r{}c{}", self.value, rows, cols)
+    }
+}
+
+/// Which links a chain may use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainKind {
+    /// One value only: strong links in units, weak links between cells
+    XChain,
+    /// Strong links inside cells with two pencil marks, weak links between cells of one value
+    XYChain,
+    /// Any strong and weak links, including grouped nodes
+    Aic,
+}
+
+/// How a chain ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChainShape {
+    /// Two different ends, one of them is true
+    Open,
+    /// The end weakly links back to the start, every weak link is also strong
+    Continuous,
+    /// The chain returns to its start on strong links, the start must be true
+    Discontinuous,
+    /// The chain leaves and returns to its start on weak links, the start must be false
+    DiscontinuousWeak,
+}
+
+/// What a chain proves: its shape, eliminations and placements
+type Conclusion = (ChainShape, Vec<(u8, u8)>, Vec<(u8, u8)>);
+
+/// Join two nodes in an adjacency list, once
+fn add_link(list: &mut [Vec<usize>], a: usize, b: usize) {
+    if !list[a].contains(&b) {
+        list[a].push(b);
+        list[b].push(a);
+    }
+}
+
+/// Nodes and links available to a chain
+struct LinkGraph {
+    nodes: Vec<ChainNode>,
+    strong: Vec<Vec<usize>>,
+    weak: Vec<Vec<usize>>,
+}
+
+impl LinkGraph {
+    fn build(pencil_values: &[Candidates], kind: ChainKind) -> LinkGraph {
+        let mut nodes: Vec<ChainNode> = (0..81u8)
+            .flat_map(|c| {
+                pencil_values[c as usize]
+                    .iter()
+                    .map(move |value| ChainNode {
+                        value,
+                        cells: vec![c],
+                    })
+            })
+            .collect();
+        if kind == ChainKind::Aic {
+            // Groups of two or three cells where a box crosses a row|col
+            for box_id in 0..9 {
+                let c_box = get_unit(UnitKind::Box, box_id);
+                for kind in [UnitKind::Row, UnitKind::Col] {
+                    for line_id in 0..9 {
+                        let line = get_unit(kind, line_id);
+                        for value in 1..10u8 {
+                            let cells: Vec<u8> = c_box
+                                .cells
+                                .iter()
+                                .copied()
+                                .filter(|c| line.cells.contains(c))
+                                .filter(|&c| pencil_values[c as usize].contains(value))
+                                .collect();
+                            if cells.len() >= 2 {
+                                nodes.push(ChainNode { value, cells });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut strong = vec![Vec::new(); nodes.len()];
+        let mut weak = vec![Vec::new(); nodes.len()];
+        for a in 0..nodes.len() {
+            for b in a + 1..nodes.len() {
+                let (na, nb) = (&nodes[a], &nodes[b]);
+                if na.value == nb.value {
+                    let disjoint = !na.overlaps(nb);
+                    let all_see = na
+                        .cells
+                        .iter()
+                        .all(|&x| nb.cells.iter().all(|&y| cells_see(x, y)));
+                    if disjoint && all_see {
+                        add_link(&mut weak, a, b);
+                    }
+                } else if na.cells.len() == 1 && na.cells == nb.cells {
+                    let cell = na.cells[0] as usize;
+                    if kind != ChainKind::XChain {
+                        if kind == ChainKind::Aic {
+                            add_link(&mut weak, a, b);
+                        }
+                        if pencil_values[cell].len() == 2 {
+                            add_link(&mut strong, a, b);
+                        }
+                    }
+                }
+            }
+        }
+        if kind != ChainKind::XYChain {
+            // Strong links inside units: two nodes covering every mark of the value in the unit
+            for unit in get_all_units() {
+                for value in 1..10u8 {
+                    let marked: Vec<u8> = unit
+                        .cells
+                        .iter()
+                        .copied()
+                        .filter(|&c| pencil_values[c as usize].contains(value))
+                        .collect();
+                    let inside: Vec<usize> = (0..nodes.len())
+                        .filter(|&n| {
+                            nodes[n].value == value
+                                && nodes[n].cells.iter().all(|c| unit.cells.contains(c))
+                        })
+                        .collect();
+                    for (i, &a) in inside.iter().enumerate() {
+                        for &b in inside[i + 1..].iter() {
+                            let (na, nb) = (&nodes[a], &nodes[b]);
+                            if !na.overlaps(nb) && na.cells.len() + nb.cells.len() == marked.len() {
+                                add_link(&mut strong, a, b);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        LinkGraph {
+            nodes,
+            strong,
+            weak,
+        }
+    }
+}
+
+impl LinkGraph {
+    /// Breadth first search from `start`, alternating strong and weak links.
+    /// Assumed false, the start opens chains and strong-strong loops. Assumed true, it only opens
+    /// weak-weak loops that come back to it false.
+    /// Returns the shortest chain proving something, as node indices from `start`
+    fn search(
+        &self,
+        pencil_values: &[Candidates],
+        start: usize,
+        start_true: bool,
+        max_nodes: usize,
+    ) -> Option<(Vec<usize>, Conclusion)> {
+        // States are node * 2 + 1 when the node is true, node * 2 when false
+        let mut parent: Vec<Option<usize>> = vec![None; self.nodes.len() * 2];
+        let mut depth: Vec<usize> = vec![usize::MAX; self.nodes.len() * 2];
+        let first = start * 2 + usize::from(start_true);
+        depth[first] = 0;
+        let mut queue = VecDeque::from([first]);
+        while let Some(state) = queue.pop_front() {
+            let (node, on) = (state / 2, state % 2 == 1);
+            if depth[state] + 2 > max_nodes {
+                continue;
+            }
+            let next = if on {
+                &self.weak[node]
+            } else {
+                &self.strong[node]
+            };
+            for &other in next.iter() {
+                let other_state = other * 2 + usize::from(!on);
+                if depth[other_state] != usize::MAX {
+                    continue;
+                }
+                depth[other_state] = depth[state] + 1;
+                parent[other_state] = Some(state);
+                queue.push_back(other_state);
+                if start_true {
+                    // Back at the start, false: assuming it true was a contradiction
+                    if on && other == start {
+                        let path = Self::path(&parent, other_state);
+                        return Some((path, self.conclude_weak_loop(start)));
+                    }
+                    continue;
+                }
+                if on {
+                    continue;
+                }
+                // Reached a true node: the start or this node must be true
+                let path = Self::path(&parent, other_state);
+                if let Some(conclusion) = self.conclude(pencil_values, &path) {
+                    return Some((path, conclusion));
+                }
+            }
+        }
+        None
+    }
+
+    /// Nodes of the chain leading to a search state, from the start
+    fn path(parent: &[Option<usize>], state: usize) -> Vec<usize> {
+        let mut path = vec![state / 2];
+        let mut current = state;
+        while let Some(previous) = parent[current] {
+            path.push(previous / 2);
+            current = previous;
+        }
+        path.reverse();
+        path
+    }
+
+    /// What a weak-weak loop proves: the value is removed from every cell of its start
+    fn conclude_weak_loop(&self, start: usize) -> Conclusion {
+        let node = &self.nodes[start];
+        let eliminations = node.cells.iter().map(|&c| (c, node.value)).collect();
+        (ChainShape::DiscontinuousWeak, eliminations, Vec::new())
+    }
+
+    /// Find what a chain starting and ending on strong links proves
+    fn conclude(&self, pencil_values: &[Candidates], path: &[usize]) -> Option<Conclusion> {
+        let (first, last) = (path[0], path[path.len() - 1]);
+        let start = &self.nodes[first];
+        if first == last {
+            // Assuming the start false proves it true
+            if start.cells.len() != 1 {
+                return None;
+            }
+            let placement = (start.cells[0], start.value);
+            return Some((ChainShape::Discontinuous, Vec::new(), vec![placement]));
+        }
+        if path.len() >= 4 && self.weak[last].contains(&first) && self.distinct(path) {
+            let mut eliminations: Vec<(u8, u8)> = Vec::new();
+            for i in (1..path.len()).step_by(2) {
+                let (a, b) = (path[i], path[(i + 1) % path.len()]);
+                for elim in self.weak_link_eliminations(pencil_values, a, b) {
+                    if !eliminations.contains(&elim) {
+                        eliminations.push(elim);
+                    }
+                }
+            }
+            if !eliminations.is_empty() {
+                return Some((ChainShape::Continuous, eliminations, Vec::new()));
+            }
+        }
+        let eliminations = self.either_eliminations(pencil_values, first, last);
+        if eliminations.is_empty() {
+            return None;
+        }
+        Some((ChainShape::Open, eliminations, Vec::new()))
+    }
+
+    /// Check the nodes of a loop are all different and never share a pencil mark
+    fn distinct(&self, path: &[usize]) -> bool {
+        path.iter().enumerate().all(|(i, &a)| {
+            path[i + 1..]
+                .iter()
+                .all(|&b| a != b && !self.nodes[a].overlaps(&self.nodes[b]))
+        })
+    }
+
+    /// Pencil marks removed when exactly one of two weakly linked nodes is true
+    fn weak_link_eliminations(
+        &self,
+        pencil_values: &[Candidates],
+        a: usize,
+        b: usize,
+    ) -> Vec<(u8, u8)> {
+        let (na, nb) = (&self.nodes[a], &self.nodes[b]);
+        if na.value == nb.value {
+            let cells: Vec<u8> = na.cells.iter().chain(nb.cells.iter()).copied().collect();
+            eliminate_seen(pencil_values, na.value, &cells)
+                .into_iter()
+                .filter(|(c, _)| !cells.contains(c))
+                .collect()
+        } else {
+            // Two values of one cell, every other value of the cell goes
+            let cell = na.cells[0];
+            pencil_values[cell as usize]
+                .iter()
+                .filter(|&v| v != na.value && v != nb.value)
+                .map(|v| (cell, v))
+                .collect()
+        }
+    }
+
+    /// Pencil marks removed when at least one of two nodes is true
+    fn either_eliminations(
+        &self,
+        pencil_values: &[Candidates],
+        a: usize,
+        b: usize,
+    ) -> Vec<(u8, u8)> {
+        let (na, nb) = (&self.nodes[a], &self.nodes[b]);
+        if na.value == nb.value {
+            let cells: Vec<u8> = na.cells.iter().chain(nb.cells.iter()).copied().collect();
+            return eliminate_seen(pencil_values, na.value, &cells)
+                .into_iter()
+                .filter(|(c, _)| !cells.contains(c))
+                .collect();
+        }
+        if na.cells.len() == 1 && na.cells == nb.cells {
+            return self.weak_link_eliminations(pencil_values, a, b);
+        }
+        // A single cell node can not hold the other value if it sees all of the other node
+        let mut eliminations = Vec::new();
+        for (single, other) in [(na, nb), (nb, na)] {
+            if single.cells.len() != 1 {
+                continue;
+            }
+            let cell = single.cells[0];
+            if pencil_values[cell as usize].contains(other.value)
+                && other.cells.iter().all(|&c| cells_see(cell, c))
+            {
+                eliminations.push((cell, other.value));
+            }
+        }
+        eliminations
+    }
+
+    /// Write a chain in Eureka notation, links alternating strong (`=`) and weak (`-`)
+    /// The first link is weak when the chain starts from a true node
+    fn eureka(&self, path: &[usize], closed: bool, start_true: bool) -> String {
+        let mut chain = self.nodes[path[0]].to_string();
+        for (i, &node) in path.iter().enumerate().skip(1) {
+            chain.push(if (i % 2 == 1) != start_true { '=' } else { '-' });
+            chain.push_str(&self.nodes[node].to_string());
+        }
+        if closed {
+            chain.push('-');
+            chain.push_str(&self.nodes[path[0]].to_string());
+        }
+        chain
+    }
+}
+
+/// Alternating Inference Chain search limited to one kind of links
+/// Finds the shortest chain that proves something, including continuous and discontinuous nice loops
+/// A weak-weak loop is two links longer than the open chain between its ends, which removes the
+/// same start candidate, so it is only reported when no shorter chain proves anything
+pub struct Chain {
+    pub kind: ChainKind,
+}
+
+impl Technique for Chain {
+    fn name(&self) -> &'static str {
+        match self.kind {
+            ChainKind::XChain => "X-Chain",
+            ChainKind::XYChain => "XY-Chain",
+            ChainKind::Aic => "AIC",
+        }
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let graph = LinkGraph::build(pencil_values, self.kind);
+        let mut best: Option<(Vec<usize>, Conclusion)> = None;
+        for start_true in [false, true] {
+            for start in 0..graph.nodes.len() {
+                let max_nodes = best
+                    .as_ref()
+                    .map_or(MAX_CHAIN_NODES, |(path, _)| path.len() - 1);
+                if let Some(found) = graph.search(pencil_values, start, start_true, max_nodes) {
+                    best = Some(found);
+                }
+            }
+        }
+        let (path, (shape, eliminations, placements)) = best?;
+        let grouped = path.iter().any(|&n| graph.nodes[n].cells.len() > 1);
+        let technique = match (self.kind, grouped) {
+            (ChainKind::Aic, true) => "Grouped AIC",
+            _ => self.name(),
+        };
+        let mut conclusion: Vec<String> = eliminations
+            .iter()
+            .map(|&(c, v)| format!("{}<>{}", cell_name(c), v))
+            .collect();
+        conclusion.extend(
+            placements
+                .iter()
+                .map(|&(c, v)| format!("{}={}", cell_name(c), v)),
+        );
+        let prefix = match shape {
+            ChainShape::Open => "",
+            ChainShape::Continuous => "Continuous Nice Loop ",
+            ChainShape::Discontinuous | ChainShape::DiscontinuousWeak => "Discontinuous Nice Loop ",
+        };
+        let step = Step {
+            technique,
+            description: format!(
+                "{}{} => {}",
+                prefix,
+                graph.eureka(
+                    &path,
+                    shape == ChainShape::Continuous,
+                    shape == ChainShape::DiscontinuousWeak
+                ),
+                conclusion.join(", ")
+            ),
+            eliminations,
+            placements,
+        };
+        Some(step)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_ctrl::get_cell_id;
+    use crate::techniques::testing::marks;
+
+    fn node(graph: &LinkGraph, row: u8, col: u8, value: u8) -> usize {
+        let cell = get_cell_id(row, col);
+        (0..graph.nodes.len())
+            .find(|&n| graph.nodes[n].value == value && graph.nodes[n].cells == [cell])
+            .unwrap()
+    }
+
+    #[test]
+    fn continuous_loop() {
+        // Rows 1 and 5 hold 1 only in cols 1 and 5, the loop clears the rest of both cols
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 4, 1),
+            (4, 0, 1),
+            (4, 4, 1),
+            (7, 0, 1),
+            (7, 4, 1),
+            (7, 8, 1),
+        ]);
+        let chain = Chain {
+            kind: ChainKind::XChain,
+        };
+        let step = chain.find(&[0; 81], &pencil_values).unwrap();
+        assert!(step.description.starts_with("Continuous Nice Loop "));
+        let mut eliminations = step.eliminations.clone();
+        eliminations.sort_unstable();
+        assert_eq!(
+            eliminations,
+            vec![(get_cell_id(7, 0), 1), (get_cell_id(7, 4), 1)]
+        );
+        assert!(step.placements.is_empty());
+    }
+
+    #[test]
+    fn discontinuous_strong_loop() {
+        // r1c1 false makes r1c2 true, which makes r2c1 false and r1c1 true again
+        let pencil_values = marks(&[(0, 0, 1), (0, 1, 1), (1, 0, 1)]);
+        let graph = LinkGraph::build(&pencil_values, ChainKind::XChain);
+        let (a, b, c) = (
+            node(&graph, 0, 0, 1),
+            node(&graph, 0, 1, 1),
+            node(&graph, 1, 0, 1),
+        );
+        let path = [a, b, c, a];
+        let (shape, eliminations, placements) = graph.conclude(&pencil_values, &path).unwrap();
+        assert_eq!(shape, ChainShape::Discontinuous);
+        assert!(eliminations.is_empty());
+        assert_eq!(placements, vec![(0, 1)]);
+        assert_eq!(
+            graph.eureka(&path, false, false),
+            "(1)r1c1=(1)r1c2-(1)r2c1=(1)r1c1"
+        );
+    }
+
+    #[test]
+    fn discontinuous_weak_loop() {
+        // r1c1 true makes r1c5 false, r5c5 true, r5c2 false, r6c1 true and r1c1 false
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 4, 1),
+            (0, 8, 1),
+            (4, 4, 1),
+            (4, 1, 1),
+            (5, 0, 1),
+        ]);
+        let graph = LinkGraph::build(&pencil_values, ChainKind::XChain);
+        let start = node(&graph, 0, 0, 1);
+        let (path, (shape, eliminations, placements)) = graph
+            .search(&pencil_values, start, true, MAX_CHAIN_NODES)
+            .unwrap();
+        assert_eq!(shape, ChainShape::DiscontinuousWeak);
+        assert_eq!(eliminations, vec![(0, 1)]);
+        assert!(placements.is_empty());
+        assert_eq!((path[0], path[path.len() - 1]), (start, start));
+        let eureka = graph.eureka(&path, false, true);
+        assert!(eureka.starts_with("(1)r1c1-") && eureka.ends_with("-(1)r1c1"));
+    }
+
+    #[test]
+    fn start_true_without_loop() {
+        let pencil_values = marks(&[(0, 0, 1), (0, 4, 1), (4, 4, 1), (4, 0, 1)]);
+        let graph = LinkGraph::build(&pencil_values, ChainKind::XChain);
+        let start = node(&graph, 0, 0, 1);
+        // Every link of this X-Wing is strong, assuming r1c1 true never makes it false
+        assert_eq!(
+            graph.search(&pencil_values, start, true, MAX_CHAIN_NODES),
+            None
+        );
+    }
+}
@@ -0,0 +1,356 @@
+//! Simple coloring and multi-coloring of conjugate pair chains
+//!
+//! The conjugate pairs of a value join cells into clusters. Walking a cluster, the cells alternate
+//! between two colors: all cells of one color hold the value, all cells of the other do not.
+
+use std::fmt;
+
+use crate::board_ctrl::{Size, cells_see, get_board_row, get_row_cells};
+use crate::candidates::Candidates;
+use crate::symbols::Symbols;
+use crate::techniques::{Step, Technique, find_conjugate_pairs, format_cells};
+
+/// A cluster of cells joined by conjugate pairs of one value, split into its two colors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorCluster {
+    pub value: u8,
+    pub colors: [Vec<u8>; 2],
+}
+
+impl ColorCluster {
+    /// Color (0 or 1) of a cell, if it is in the cluster
+    pub fn color_of(&self, cell_id: u8) -> Option<usize> {
+        (0..2).find(|&color| self.colors[color].contains(&cell_id))
+    }
+
+    /// Check if a cell sees any cell of the given color
+    pub fn sees_color(&self, cell_id: u8, color: usize) -> bool {
+        self.colors[color].iter().any(|&c| cells_see(cell_id, c))
+    }
+}
+
+impl fmt::Display for ColorCluster {
+    /// Ex: `4: +{r1c1,r3c5} -{r2c2,r3c1}`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: +{{{}}} -{{{}}}",
+            self.value,
+            format_cells(&self.colors[0]),
+            format_cells(&self.colors[1])
+        )
+    }
+}
+
+/// Color every cluster of conjugate pairs for a value
+///
+/// Parameters:
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+/// - `value: u8` - value to color
+pub fn color_value(pencil_values: &[Candidates], value: u8) -> Vec<ColorCluster> {
+    let links = find_conjugate_pairs(pencil_values, value);
+    let mut colored: Vec<Option<usize>> = vec![None; 81];
+    let mut clusters = Vec::new();
+    for start in links.iter().map(|link| link.cells[0]) {
+        if colored[start as usize].is_some() {
+            continue;
+        }
+        let mut cluster = ColorCluster {
+            value,
+            colors: [Vec::new(), Vec::new()],
+        };
+        colored[start as usize] = Some(0);
+        let mut queue = vec![start];
+        while let Some(cell) = queue.pop() {
+            let color = colored[cell as usize].unwrap();
+            cluster.colors[color].push(cell);
+            for link in links.iter().filter(|link| link.cells.contains(&cell)) {
+                let other = if link.cells[0] == cell {
+                    link.cells[1]
+                } else {
+                    link.cells[0]
+                };
+                if colored[other as usize].is_none() {
+                    colored[other as usize] = Some(1 - color);
+                    queue.push(other);
+                }
+            }
+        }
+        cluster.colors[0].sort_unstable();
+        cluster.colors[1].sort_unstable();
+        clusters.push(cluster);
+    }
+    clusters
+}
+
+/// Print the board with a cluster's colors
+/// Cells of the first color are marked `+`, the second `-`, and other cells marked for the value `.`
+///
+/// Expects the parameters:
+/// - `board: &[u8]`  - representing board in 1D array
+/// - `pencil_values: &[Candidates]` - reference to current pencil marks
+/// - `cluster: &ColorCluster` - coloring to show
+pub fn print_coloring(board: &[u8], pencil_values: &[Candidates], cluster: &ColorCluster) {
+    let symbols = Symbols::for_size(Size::CLASSIC);
+    println!(" X  0  1  2   3  4  5   6  7  8");
+    print!("Y");
+    for row_id in 0..9 {
+        if row_id % 3 == 0 {
+            println!(" -------------------------------");
+        }
+        print!("{} ", row_id);
+        let row = get_board_row(board, row_id);
+        for (i, cell_id) in get_row_cells(row_id).iter().copied().enumerate() {
+            let cell = match cluster.color_of(cell_id) {
+                Some(0) => '+',
+                Some(_) => '-',
+                None if row[i] != 0 => symbols.symbol(row[i]).unwrap_or('?'),
+                None if pencil_values[cell_id as usize].contains(cluster.value) => '.',
+                None => ' ',
+            };
+            if i % 3 == 0 {
+                print!("| {}, ", cell);
+            } else if i % 3 == 1 {
+                print!("{}, ", cell);
+            } else if i % 3 == 2 {
+                print!("{} ", cell);
+            }
+        }
+        println!("|");
+    }
+    println!("  -------------------------------");
+}
+
+/// Cells marked for the value that see both given colors
+fn trapped(
+    pencil_values: &[Candidates],
+    value: u8,
+    a: (&ColorCluster, usize),
+    b: (&ColorCluster, usize),
+) -> Vec<(u8, u8)> {
+    (0..81u8)
+        .filter(|&c| pencil_values[c as usize].contains(value))
+        .filter(|&c| !a.0.colors[a.1].contains(&c) && !b.0.colors[b.1].contains(&c))
+        .filter(|&c| a.0.sees_color(c, a.1) && b.0.sees_color(c, b.1))
+        .map(|c| (c, value))
+        .collect()
+}
+
+/// Build a coloring step
+fn coloring_step(
+    technique: &'static str,
+    reason: String,
+    clusters: &[&ColorCluster],
+    eliminations: Vec<(u8, u8)>,
+) -> Step {
+    let shown: Vec<String> = clusters.iter().map(|c| c.to_string()).collect();
+    Step {
+        technique,
+        description: format!("{}, {}", shown.join(" and "), reason),
+        eliminations,
+        placements: Vec::new(),
+    }
+}
+
+/// Simple coloring
+/// Color wrap: two cells of one color see each other, so that color is false.
+/// Color trap: a cell sees both colors, so it can not hold the value
+pub struct SimpleColoring;
+
+impl Technique for SimpleColoring {
+    fn name(&self) -> &'static str {
+        "Simple Coloring"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for value in 1..10u8 {
+            for cluster in color_value(pencil_values, value) {
+                for color in 0..2 {
+                    let cells = &cluster.colors[color];
+                    let wrapped = cells
+                        .iter()
+                        .any(|&a| cells.iter().any(|&b| cells_see(a, b)));
+                    if wrapped {
+                        let sign = if color == 0 { '+' } else { '-' };
+                        return Some(coloring_step(
+                            "Color Wrap",
+                            format!("two {} cells see each other", sign),
+                            &[&cluster],
+                            cells.iter().map(|&c| (c, value)).collect(),
+                        ));
+                    }
+                }
+                let eliminations = trapped(pencil_values, value, (&cluster, 0), (&cluster, 1));
+                if !eliminations.is_empty() {
+                    return Some(coloring_step(
+                        "Color Trap",
+                        "cells seeing both colors".to_string(),
+                        &[&cluster],
+                        eliminations,
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Multi-coloring between two clusters of the same value
+/// When a color of one cluster sees a color of the other, the two can not both be true,
+/// so one of their opposite colors is true and cells seeing both opposites lose the value.
+/// When a color sees both colors of the other cluster, it is false
+pub struct MultiColoring;
+
+impl Technique for MultiColoring {
+    fn name(&self) -> &'static str {
+        "Multi-Coloring"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for value in 1..10u8 {
+            let clusters = color_value(pencil_values, value);
+            for (i, a) in clusters.iter().enumerate() {
+                for (j, b) in clusters.iter().enumerate() {
+                    if i == j {
+                        continue;
+                    }
+                    for color_a in 0..2 {
+                        let sees = |color_b: usize| {
+                            a.colors[color_a].iter().any(|&c| b.sees_color(c, color_b))
+                        };
+                        if sees(0) && sees(1) {
+                            return Some(coloring_step(
+                                self.name(),
+                                format!(
+                                    "{} sees both colors of the second cluster",
+                                    format_cells(&a.colors[color_a])
+                                ),
+                                &[a, b],
+                                a.colors[color_a].iter().map(|&c| (c, value)).collect(),
+                            ));
+                        }
+                        for color_b in 0..2 {
+                            if j < i || !sees(color_b) {
+                                continue;
+                            }
+                            let eliminations =
+                                trapped(pencil_values, value, (a, 1 - color_a), (b, 1 - color_b));
+                            if !eliminations.is_empty() {
+                                return Some(coloring_step(
+                                    self.name(),
+                                    format!(
+                                        "{} sees {}, cells seeing both opposite colors",
+                                        format_cells(&a.colors[color_a]),
+                                        format_cells(&b.colors[color_b])
+                                    ),
+                                    &[a, b],
+                                    eliminations,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_ctrl::get_cell_id;
+    use crate::techniques::testing::{at, marks};
+
+    #[test]
+    fn color_value_alternates_along_pairs() {
+        // r1c1-r1c5 in row 1, r1c5-r5c5 in col 5, and r9c9 alone
+        let pencil_values = marks(&[(0, 0, 1), (0, 4, 1), (4, 4, 1), (8, 8, 1)]);
+        let clusters = color_value(&pencil_values, 1);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].to_string(), "1: +{r1c1,r5c5} -{r1c5}");
+        assert_eq!(clusters[0].color_of(get_cell_id(4, 4)), Some(0));
+        assert_eq!(clusters[0].color_of(get_cell_id(8, 8)), None);
+    }
+
+    #[test]
+    fn color_wrap() {
+        // r1c1 and r2c3 get the same color and both sit in box 1, with r3c2 keeping it from a pair
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 4, 1),
+            (4, 4, 1),
+            (4, 2, 1),
+            (1, 2, 1),
+            (2, 1, 1),
+        ]);
+        let step = SimpleColoring.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Color Wrap");
+        assert_eq!(
+            step.description,
+            "1: +{r1c1,r2c3,r5c5} -{r1c5,r5c3}, two + cells see each other"
+        );
+        assert_eq!(step.eliminations, [at(0, 0, 1), at(1, 2, 1), at(4, 4, 1)]);
+    }
+
+    #[test]
+    fn color_trap() {
+        // r6c1 sees r1c1 in col 1 and r6c6 in row 6, which have opposite colors
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 4, 1),
+            (4, 4, 1),
+            (5, 5, 1),
+            (5, 0, 1),
+            (8, 0, 1),
+            (5, 8, 1),
+        ]);
+        let step = SimpleColoring.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Color Trap");
+        assert_eq!(step.eliminations, [at(5, 0, 1)]);
+    }
+
+    #[test]
+    fn multi_coloring_color_sees_both_colors() {
+        // r1c1 sees r3c2 in box 1 and r7c1 in col 1, the two colors of the second cluster
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 4, 1),
+            (2, 1, 1),
+            (2, 6, 1),
+            (6, 6, 1),
+            (6, 0, 1),
+            (4, 0, 1),
+            (1, 2, 1),
+        ]);
+        let step = MultiColoring.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "1: +{r1c1} -{r1c5} and 1: +{r3c2,r7c7} -{r3c7,r7c1}, \
+             r1c1 sees both colors of the second cluster"
+        );
+        assert_eq!(step.eliminations, [at(0, 0, 1)]);
+    }
+
+    #[test]
+    fn multi_coloring_opposite_colors() {
+        // r1c1 sees r3c2, so r1c9 or r8c2 holds the 1 and r8c9 sees both
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 8, 1),
+            (2, 1, 1),
+            (7, 1, 1),
+            (1, 2, 1),
+            (7, 8, 1),
+            (7, 4, 1),
+            (4, 8, 1),
+        ]);
+        let step = MultiColoring.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "1: +{r1c1} -{r1c9} and 1: +{r3c2} -{r8c2}, \
+             r1c1 sees r3c2, cells seeing both opposite colors"
+        );
+        assert_eq!(step.eliminations, [at(7, 8, 1)]);
+    }
+}
@@ -0,0 +1,246 @@
+//! Basic fish (X-Wing, Swordfish, Jellyfish) with finned and sashimi variants
+//!
+//! A fish picks `size` base lines (rows or cols) where a value's pencil marks all fall in the same
+//! `size` cover lines of the other direction. The value then fills one cell per base line inside
+//! the cover lines, so it can be removed from the rest of the cover lines.
+//! Extra marks in the base lines (fins) are allowed when they share a box: either a fin is true,
+//! or the fish holds, so only cells of the cover lines in the fin box that see every fin lose the value.
+
+use crate::board_ctrl::{UnitKind, get_box_id, get_cell_id};
+use crate::candidates::Candidates;
+use crate::techniques::{Step, Technique, combinations, format_cells};
+
+/// Fish of a given size, optionally allowing fins
+pub struct Fish {
+    pub size: usize,
+    pub finned: bool,
+}
+
+impl Fish {
+    /// Cell idx of the crossing between a base line and a cover line
+    fn cell(base_kind: UnitKind, base: u8, cover: u8) -> u8 {
+        match base_kind {
+            UnitKind::Row => get_cell_id(base, cover),
+            _ => get_cell_id(cover, base),
+        }
+    }
+
+    /// Name of the fish, with `sashimi` only meaningful for finned fish
+    fn fish_name(&self, sashimi: bool) -> &'static str {
+        match (self.size, self.finned, sashimi) {
+            (2, false, _) => "X-Wing",
+            (3, false, _) => "Swordfish",
+            (4, false, _) => "Jellyfish",
+            (2, true, false) => "Finned X-Wing",
+            (3, true, false) => "Finned Swordfish",
+            (4, true, false) => "Finned Jellyfish",
+            (2, true, true) => "Sashimi X-Wing",
+            (3, true, true) => "Sashimi Swordfish",
+            (4, true, true) => "Sashimi Jellyfish",
+            (_, false, _) => "Fish",
+            (_, true, _) => "Finned Fish",
+        }
+    }
+
+    /// Search one orientation of the fish for one value
+    fn find_fish(
+        &self,
+        pencil_values: &[Candidates],
+        base_kind: UnitKind,
+        value: u8,
+    ) -> Option<Step> {
+        // For each base line, a bit flag of the cover lines holding a mark for the value
+        let positions: [u16; 9] = std::array::from_fn(|base| {
+            (0..9u8)
+                .filter(|&cover| {
+                    pencil_values[Fish::cell(base_kind, base as u8, cover) as usize].contains(value)
+                })
+                .fold(0u16, |acc, cover| acc | (1 << cover))
+        });
+        // Fins can only add the cells of one box to a base line
+        let max_marks = if self.finned {
+            self.size + 3
+        } else {
+            self.size
+        };
+        let base_lines: Vec<u8> = (0..9u8)
+            .filter(|&b| {
+                let count = positions[b as usize].count_ones() as usize;
+                count >= 1 && count <= max_marks
+            })
+            .collect();
+
+        for base_set in combinations(&base_lines, self.size) {
+            let union = base_set
+                .iter()
+                .fold(0u16, |acc, &b| acc | positions[b as usize]);
+            let union_count = union.count_ones() as usize;
+            let cover_options: Vec<u16> = if self.finned {
+                if union_count <= self.size {
+                    continue;
+                }
+                let covers: Vec<u8> = (0..9u8).filter(|c| union & (1 << c) != 0).collect();
+                combinations(&covers, self.size)
+                    .iter()
+                    .map(|set| set.iter().fold(0u16, |acc, &c| acc | (1 << c)))
+                    .collect()
+            } else if union_count == self.size {
+                vec![union]
+            } else {
+                continue;
+            };
+
+            for cover_mask in cover_options {
+                // Every base line needs at least one mark inside the cover lines
+                if base_set
+                    .iter()
+                    .any(|&b| positions[b as usize] & cover_mask == 0)
+                {
+                    continue;
+                }
+                let fins: Vec<u8> = base_set
+                    .iter()
+                    .flat_map(|&b| {
+                        (0..9u8)
+                            .filter(move |c| positions[b as usize] & !cover_mask & (1 << c) != 0)
+                            .map(move |c| Fish::cell(base_kind, b, c))
+                    })
+                    .collect();
+                let fin_box = fins.first().map(|&f| get_box_id(f));
+                if fins.iter().any(|&f| Some(get_box_id(f)) != fin_box) {
+                    continue;
+                }
+                let eliminations: Vec<(u8, u8)> = (0..9u8)
+                    .filter(|c| cover_mask & (1 << c) != 0)
+                    .flat_map(|cover| {
+                        (0..9u8)
+                            .filter(|b| !base_set.contains(b))
+                            .map(move |b| Fish::cell(base_kind, b, cover))
+                    })
+                    .filter(|&cell| pencil_values[cell as usize].contains(value))
+                    .filter(|&cell| fin_box.is_none_or(|fb| get_box_id(cell) == fb))
+                    .map(|cell| (cell, value))
+                    .collect();
+                if eliminations.is_empty() {
+                    continue;
+                }
+
+                let sashimi = base_set
+                    .iter()
+                    .any(|&b| (positions[b as usize] & cover_mask).count_ones() == 1);
+                let (base_name, cover_name) = match base_kind {
+                    UnitKind::Row => ("rows", "cols"),
+                    _ => ("cols", "rows"),
+                };
+                let bases: Vec<String> = base_set.iter().map(|b| (b + 1).to_string()).collect();
+                let covers: Vec<String> = (0..9u8)
+                    .filter(|c| cover_mask & (1 << c) != 0)
+                    .map(|c| (c + 1).to_string())
+                    .collect();
+                let mut description = format!(
+                    "{} in base {} {} / cover {} {}",
+                    value,
+                    base_name,
+                    bases.join(","),
+                    cover_name,
+                    covers.join(",")
+                );
+                if !fins.is_empty() {
+                    description.push_str(&format!(" with fins {}", format_cells(&fins)));
+                }
+                return Some(Step {
+                    technique: self.fish_name(sashimi),
+                    description,
+                    eliminations,
+                    placements: Vec::new(),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl Technique for Fish {
+    fn name(&self) -> &'static str {
+        self.fish_name(false)
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for value in 1..10u8 {
+            for base_kind in [UnitKind::Row, UnitKind::Col] {
+                if let Some(step) = self.find_fish(pencil_values, base_kind, value) {
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::techniques::testing::{at, marks, sorted};
+
+    const FINNED: Fish = Fish {
+        size: 2,
+        finned: true,
+    };
+
+    #[test]
+    fn finned_x_wing_eliminates_in_fin_box() {
+        // 1 in rows 1 and 5 on cols 1 and 7, with a fin at r5c8
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 6, 1),
+            (4, 0, 1),
+            (4, 6, 1),
+            (4, 7, 1),
+            (3, 6, 1),
+            (5, 6, 1),
+            (7, 6, 1),
+        ]);
+        let step = FINNED.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Finned X-Wing");
+        assert_eq!(
+            step.description,
+            "1 in base rows 1,5 / cover cols 1,7 with fins r5c8"
+        );
+        // r8c7 is in the cover col but out of the fin box
+        assert_eq!(sorted(step.eliminations), [at(3, 6, 1), at(5, 6, 1)]);
+    }
+
+    #[test]
+    fn sashimi_x_wing() {
+        // Row 5 only keeps r5c7 in the cover cols, with fins at r5c8 and r5c9
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 6, 1),
+            (4, 6, 1),
+            (4, 7, 1),
+            (4, 8, 1),
+            (3, 6, 1),
+            (5, 6, 1),
+        ]);
+        let step = FINNED.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Sashimi X-Wing");
+        assert_eq!(sorted(step.eliminations), [at(3, 6, 1), at(5, 6, 1)]);
+    }
+
+    #[test]
+    fn fins_in_two_boxes_are_not_a_fish() {
+        // Fins at r5c2 and r5c8 are in different boxes
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 6, 1),
+            (4, 0, 1),
+            (4, 1, 1),
+            (4, 6, 1),
+            (4, 7, 1),
+            (3, 6, 1),
+            (5, 6, 1),
+            (7, 6, 1),
+        ]);
+        assert!(FINNED.find(&[0; 81], &pencil_values).is_none());
+    }
+}
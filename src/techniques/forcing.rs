@@ -0,0 +1,381 @@
+//! Forcing chains and Nishio, last resort techniques before guessing
+//!
+//! A branch assumes something about one pencil mark and follows it with passes of naked and
+//! hidden singles, like `pencil_in` and `write_in` do, for at most `depth` passes.
+//! - Nishio: a branch that reaches a contradiction proves its assumption false
+//! - Forcing chains: when every possible branch (each mark of a cell, each place for a value in a
+//!   unit, or a mark being on or off) agrees on something, it is true
+
+use crate::board_ctrl::{Size, cell_name, get_all_units, write_cell};
+use crate::candidates::Candidates;
+use crate::solver::{find_contradiction, hidden_singles, naked_singles, pencil_in};
+use crate::techniques::{Step, Technique};
+
+/// Passes of singles followed by each branch when no depth is given
+pub const DEFAULT_FORCING_DEPTH: usize = 8;
+
+/// What a branch assumes about a pencil mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Assumption {
+    /// The value goes in the cell
+    Place(u8, u8),
+    /// The value does not go in the cell
+    Remove(u8, u8),
+}
+
+/// Board state reached by following an assumption
+struct Branch {
+    board: Vec<u8>,
+    pencil_values: Vec<Candidates>,
+    /// The assumption, then the values written in on each pass
+    implications: Vec<String>,
+    /// Why the branch is impossible, if it is
+    contradiction: Option<String>,
+}
+
+impl Branch {
+    /// Follow an assumption for at most `depth` passes of singles
+    fn follow(
+        board: &[u8],
+        pencil_values: &[Candidates],
+        assumption: Assumption,
+        depth: usize,
+    ) -> Branch {
+        let mut board: Vec<u8> = board.to_vec();
+        let mut pencil_values: Vec<Candidates> = pencil_values.to_vec();
+        let mut implications = Vec::new();
+        match assumption {
+            Assumption::Place(cell, value) => {
+                board = write_cell(&board, cell as usize, value);
+                implications.push(format!("{}={}", cell_name(cell), value));
+            }
+            Assumption::Remove(cell, value) => {
+                pencil_values[cell as usize] -= Candidates::only(value);
+                implications.push(format!("{}<>{}", cell_name(cell), value));
+            }
+        }
+        let mut contradiction = None;
+        for pass in 0..=depth {
+            // Keep every pencil mark already removed, like the solver loop does
+            pencil_values = pencil_in(&board, Size::CLASSIC)
+                .iter()
+                .zip(pencil_values.iter())
+                .map(|(&fresh, &old)| fresh & old)
+                .collect();
+            contradiction = describe_contradiction(&board, &pencil_values);
+            if contradiction.is_some() || pass == depth {
+                break;
+            }
+            let mut singles = naked_singles(&pencil_values);
+            for (cell, value) in hidden_singles(&pencil_values, Size::CLASSIC) {
+                match singles.iter().find(|&&(c, _)| c == cell) {
+                    Some(&(_, other)) if other != value => {
+                        contradiction = Some(format!(
+                            "{} must be both {} and {}",
+                            Size::CLASSIC.cell_name(cell),
+                            other,
+                            value
+                        ));
+                    }
+                    Some(_) => {}
+                    None => singles.push((cell, value)),
+                }
+            }
+            if contradiction.is_some() || singles.is_empty() {
+                break;
+            }
+            let written: Vec<String> = singles
+                .iter()
+                .map(|&(cell, value)| format!("{}={}", Size::CLASSIC.cell_name(cell), value))
+                .collect();
+            implications.push(written.join(","));
+            for (cell, value) in singles {
+                board = write_cell(&board, cell, value);
+            }
+        }
+        Branch {
+            board,
+            pencil_values,
+            implications,
+            contradiction,
+        }
+    }
+
+    /// Check if the branch rules out a value in a cell
+    fn excludes(&self, cell: u8, value: u8) -> bool {
+        let i = cell as usize;
+        self.board[i] != value && (self.board[i] != 0 || !self.pencil_values[i].contains(value))
+    }
+
+    /// The chain of implications, ex: `r1c1=4 => r1c5=7,r3c2=1 => contradiction: ...`
+    fn describe(&self) -> String {
+        let mut chain = self.implications.join(" => ");
+        if let Some(reason) = &self.contradiction {
+            chain.push_str(&format!(" => contradiction: {}", reason));
+        }
+        chain
+    }
+}
+
+/// Explain why a board can not be solved, if it can't
+fn describe_contradiction(board: &[u8], pencil_values: &[Candidates]) -> Option<String> {
+    if let Some(cell) = find_contradiction(board, pencil_values, Size::CLASSIC) {
+        let name = Size::CLASSIC.cell_name(cell);
+        return Some(if board[cell] == 0 {
+            format!("{} has no pencil marks", name)
+        } else {
+            format!("{} repeats {}", name, board[cell])
+        });
+    }
+    for unit in get_all_units() {
+        for value in 1..10u8 {
+            let placed = unit.cells.iter().any(|&c| board[c as usize] == value);
+            let marked = unit
+                .cells
+                .iter()
+                .any(|&c| pencil_values[c as usize].contains(value));
+            if !placed && !marked {
+                return Some(format!("no place for {} in {}", value, unit));
+            }
+        }
+    }
+    None
+}
+
+/// Build the step for what every possible branch agrees on
+/// Branches reaching a contradiction are impossible and left out
+fn common_step(
+    technique: &'static str,
+    header: String,
+    board: &[u8],
+    pencil_values: &[Candidates],
+    branches: &[Branch],
+) -> Option<Step> {
+    let live: Vec<&Branch> = branches
+        .iter()
+        .filter(|b| b.contradiction.is_none())
+        .collect();
+    if live.is_empty() {
+        return None;
+    }
+    let mut placements = Vec::new();
+    let mut eliminations = Vec::new();
+    for cell in 0..81u8 {
+        if board[cell as usize] != 0 {
+            continue;
+        }
+        let value = live[0].board[cell as usize];
+        if value != 0 && live.iter().all(|b| b.board[cell as usize] == value) {
+            placements.push((cell, value));
+            continue;
+        }
+        for value in pencil_values[cell as usize] {
+            if live.iter().all(|b| b.excludes(cell, value)) {
+                eliminations.push((cell, value));
+            }
+        }
+    }
+    if placements.is_empty() && eliminations.is_empty() {
+        return None;
+    }
+    let chains: Vec<String> = branches.iter().map(|b| b.describe()).collect();
+    Some(Step {
+        technique,
+        description: format!("{}: {}", header, chains.join(" | ")),
+        eliminations,
+        placements,
+    })
+}
+
+/// Nishio
+/// Assume a value goes in a cell and follow it; reaching a contradiction removes the mark
+pub struct Nishio {
+    pub depth: usize,
+}
+
+impl Technique for Nishio {
+    fn name(&self) -> &'static str {
+        "Nishio"
+    }
+
+    fn find(&self, board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for cell in 0..81u8 {
+            for value in pencil_values[cell as usize] {
+                let assumption = Assumption::Place(cell, value);
+                let branch = Branch::follow(board, pencil_values, assumption, self.depth);
+                if branch.contradiction.is_some() {
+                    return Some(Step {
+                        technique: self.name(),
+                        description: branch.describe(),
+                        eliminations: vec![(cell, value)],
+                        placements: Vec::new(),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Which set of branches a forcing chain tries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForcingKind {
+    /// Each pencil mark of one cell
+    Cell,
+    /// Each place for one value in a box|row|col
+    Unit,
+    /// One pencil mark both on and off
+    Digit,
+}
+
+/// Forcing chains
+/// Follow every possibility of a cell, unit or pencil mark; what they all agree on is true
+pub struct ForcingChain {
+    pub kind: ForcingKind,
+    pub depth: usize,
+}
+
+impl Technique for ForcingChain {
+    fn name(&self) -> &'static str {
+        match self.kind {
+            ForcingKind::Cell => "Cell Forcing Chain",
+            ForcingKind::Unit => "Unit Forcing Chain",
+            ForcingKind::Digit => "Digit Forcing Chain",
+        }
+    }
+
+    fn find(&self, board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let follow = |assumption| Branch::follow(board, pencil_values, assumption, self.depth);
+        match self.kind {
+            ForcingKind::Cell => (0..81u8)
+                .filter(|&c| pencil_values[c as usize].len() >= 2)
+                .find_map(|cell| {
+                    let mark = pencil_values[cell as usize];
+                    let branches: Vec<Branch> = mark
+                        .iter()
+                        .map(|v| follow(Assumption::Place(cell, v)))
+                        .collect();
+                    let header = format!("{}{}", cell_name(cell), mark);
+                    common_step(self.name(), header, board, pencil_values, &branches)
+                }),
+            ForcingKind::Unit => get_all_units().iter().find_map(|unit| {
+                (1..10u8).find_map(|value| {
+                    let places: Vec<u8> = unit
+                        .cells
+                        .iter()
+                        .copied()
+                        .filter(|&c| pencil_values[c as usize].contains(value))
+                        .collect();
+                    if places.len() < 2 {
+                        return None;
+                    }
+                    let branches: Vec<Branch> = places
+                        .iter()
+                        .map(|&c| follow(Assumption::Place(c, value)))
+                        .collect();
+                    let header = format!("{} in {}", value, unit);
+                    common_step(self.name(), header, board, pencil_values, &branches)
+                })
+            }),
+            ForcingKind::Digit => (0..81u8).find_map(|cell| {
+                pencil_values[cell as usize].iter().find_map(|value| {
+                    let branches = [
+                        follow(Assumption::Place(cell, value)),
+                        follow(Assumption::Remove(cell, value)),
+                    ];
+                    let header = format!("{}={} on or off", cell_name(cell), value);
+                    common_step(self.name(), header, board, pencil_values, &branches)
+                })
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::techniques::testing::{at, cells};
+
+    /// Every pencil mark in every empty cell, but for the given `(row, col, digits)` cells
+    fn open(given: &[(u8, u8, u32)]) -> Vec<Candidates> {
+        cells(given)
+            .into_iter()
+            .map(|mark| {
+                if mark.is_empty() {
+                    Candidates::all(9)
+                } else {
+                    mark
+                }
+            })
+            .collect()
+    }
+
+    /// r1c1 can't be 1, as r1c2 and r1c3 would both need 2
+    fn pair_blocks_r1c1() -> Vec<Candidates> {
+        open(&[(0, 0, 13), (0, 1, 12), (0, 2, 12)])
+    }
+
+    /// Either r1c1=1 puts 4 in r1c5, or r1c1=2 puts 4 in r5c1; both leave r5c5=7
+    fn either_way_r5c5() -> Vec<Candidates> {
+        open(&[(0, 0, 12), (0, 4, 14), (4, 0, 24), (4, 4, 47)])
+    }
+
+    #[test]
+    fn branch_stops_after_depth_passes() {
+        let board = vec![0; 81];
+        let pencil_values = either_way_r5c5();
+        let place = Assumption::Place(at(0, 0, 1).0, 1);
+        let r5c5 = at(4, 4, 7).0 as usize;
+
+        let shallow = Branch::follow(&board, &pencil_values, place, 1);
+        assert_eq!(shallow.implications, ["r1c1=1", "r1c5=4"]);
+        assert_eq!(shallow.board[r5c5], 0);
+
+        let deep = Branch::follow(&board, &pencil_values, place, 2);
+        assert_eq!(deep.implications, ["r1c1=1", "r1c5=4", "r5c5=7"]);
+        assert_eq!(deep.board[r5c5], 7);
+        assert!(deep.contradiction.is_none());
+    }
+
+    #[test]
+    fn nishio_removes_contradicted_mark() {
+        let nishio = Nishio {
+            depth: DEFAULT_FORCING_DEPTH,
+        };
+        let step = nishio.find(&[0; 81], &pair_blocks_r1c1()).unwrap();
+        assert_eq!(step.eliminations, [at(0, 0, 1)]);
+        assert!(step.placements.is_empty());
+        assert!(step.description.starts_with("r1c1=1 => "));
+        assert!(step.description.contains(" => contradiction: "));
+    }
+
+    #[test]
+    fn cell_forcing_chain_places_agreed_value() {
+        let chain = ForcingChain {
+            kind: ForcingKind::Cell,
+            depth: DEFAULT_FORCING_DEPTH,
+        };
+        let step = chain.find(&[0; 81], &either_way_r5c5()).unwrap();
+        assert_eq!(step.technique, "Cell Forcing Chain");
+        assert_eq!(step.placements, [at(4, 4, 7)]);
+        assert_eq!(
+            step.description,
+            "r1c1{1,2}: r1c1=1 => r1c5=4 => r5c5=7 | r1c1=2 => r5c1=4 => r5c5=7"
+        );
+    }
+
+    #[test]
+    fn digit_forcing_chain_keeps_surviving_branch() {
+        let chain = ForcingChain {
+            kind: ForcingKind::Digit,
+            depth: DEFAULT_FORCING_DEPTH,
+        };
+        let step = chain.find(&[0; 81], &pair_blocks_r1c1()).unwrap();
+        // Placing 1 in r1c1 is contradicted, removing it leaves r1c1=3
+        assert_eq!(step.placements, [at(0, 0, 3)]);
+        let (placed, removed) = step.description.split_once(" | ").unwrap();
+        assert!(placed.starts_with("r1c1=1 on or off: r1c1=1 => "));
+        assert!(placed.contains(" => contradiction: "));
+        assert_eq!(removed, "r1c1<>1 => r1c1=3");
+    }
+}
@@ -0,0 +1,174 @@
+//! Locked candidates where a box crosses a row or col
+
+use crate::board_ctrl::{UnitKind, get_box_id, get_cell_col, get_cell_row, get_unit};
+use crate::candidates::Candidates;
+use crate::techniques::{Step, Technique, format_cells};
+
+/// Cells of a unit holding a pencil mark for the value
+fn marked_cells(cells: &[u8], pencil_values: &[Candidates], value: u8) -> Vec<u8> {
+    cells
+        .iter()
+        .copied()
+        .filter(|&c| pencil_values[c as usize].contains(value))
+        .collect()
+}
+
+/// Build the step removing `value` from `targets`, if any of them are marked
+fn locked_step(
+    technique: &'static str,
+    description: String,
+    targets: impl Iterator<Item = u8>,
+    pencil_values: &[Candidates],
+    value: u8,
+) -> Option<Step> {
+    let eliminations: Vec<(u8, u8)> = targets
+        .filter(|&c| pencil_values[c as usize].contains(value))
+        .map(|c| (c, value))
+        .collect();
+    if eliminations.is_empty() {
+        return None;
+    }
+    Some(Step {
+        technique,
+        description,
+        eliminations,
+        placements: Vec::new(),
+    })
+}
+
+/// Pointing pairs and triples
+/// When a value in a box can only go in one row|col, it can not go anywhere else in that row|col
+pub struct Pointing;
+
+impl Technique for Pointing {
+    fn name(&self) -> &'static str {
+        "Pointing"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for box_id in 0..9 {
+            let c_box = get_unit(UnitKind::Box, box_id);
+            for value in 1..10u8 {
+                let cells = marked_cells(c_box.cells, pencil_values, value);
+                if cells.len() < 2 {
+                    continue;
+                }
+                let technique = if cells.len() == 2 {
+                    "Pointing Pair"
+                } else {
+                    "Pointing Triple"
+                };
+                let row_id = get_cell_row(cells[0]);
+                let col_id = get_cell_col(cells[0]);
+                let line = if cells.iter().all(|&c| get_cell_row(c) == row_id) {
+                    get_unit(UnitKind::Row, row_id)
+                } else if cells.iter().all(|&c| get_cell_col(c) == col_id) {
+                    get_unit(UnitKind::Col, col_id)
+                } else {
+                    continue;
+                };
+                let step = locked_step(
+                    technique,
+                    format!(
+                        "{} in {} is locked to {} ({})",
+                        value,
+                        c_box,
+                        line,
+                        format_cells(&cells)
+                    ),
+                    line.cells
+                        .iter()
+                        .copied()
+                        .filter(|&c| get_box_id(c) != box_id),
+                    pencil_values,
+                    value,
+                );
+                if step.is_some() {
+                    return step;
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Box-line reduction (claiming)
+/// When a value in a row|col can only go in one box, it can not go anywhere else in that box
+pub struct BoxLineReduction;
+
+impl Technique for BoxLineReduction {
+    fn name(&self) -> &'static str {
+        "Box-Line Reduction"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for kind in [UnitKind::Row, UnitKind::Col] {
+            for line_id in 0..9 {
+                let line = get_unit(kind, line_id);
+                for value in 1..10u8 {
+                    let cells = marked_cells(line.cells, pencil_values, value);
+                    if cells.len() < 2 {
+                        continue;
+                    }
+                    let box_id = get_box_id(cells[0]);
+                    if cells.iter().any(|&c| get_box_id(c) != box_id) {
+                        continue;
+                    }
+                    let c_box = get_unit(UnitKind::Box, box_id);
+                    let step = locked_step(
+                        self.name(),
+                        format!(
+                            "{} in {} is locked to {} ({})",
+                            value,
+                            line,
+                            c_box,
+                            format_cells(&cells)
+                        ),
+                        c_box
+                            .cells
+                            .iter()
+                            .copied()
+                            .filter(|&c| !line.cells.contains(&c)),
+                        pencil_values,
+                        value,
+                    );
+                    if step.is_some() {
+                        return step;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::techniques::testing::{at, marks};
+
+    #[test]
+    fn pointing_pair() {
+        // 1 in box 1 is only on row 1, so r1c5 can't be 1
+        let pencil_values = marks(&[(0, 0, 1), (0, 1, 1), (0, 4, 1)]);
+        let step = Pointing.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Pointing Pair");
+        assert_eq!(
+            step.description,
+            "1 in box 1 is locked to row 1 (r1c1,r1c2)"
+        );
+        assert_eq!(step.eliminations, [at(0, 4, 1)]);
+    }
+
+    #[test]
+    fn box_line_reduction() {
+        // 1 in row 1 is only in box 1, so r2c2 can't be 1
+        let pencil_values = marks(&[(0, 0, 1), (0, 1, 1), (1, 1, 1)]);
+        let step = BoxLineReduction.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "1 in row 1 is locked to box 1 (r1c1,r1c2)"
+        );
+        assert_eq!(step.eliminations, [at(1, 1, 1)]);
+    }
+}
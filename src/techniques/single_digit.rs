@@ -0,0 +1,318 @@
+//! Single value patterns built from two strong links (conjugate pairs)
+//!
+//! Two conjugate pairs of the same value are joined when one end of each see each other (a weak
+//! link). Both joined ends can not hold the value, so one of the two far ends must, and the value
+//! is removed from every cell seeing both far ends.
+
+use crate::board_ctrl::{
+    UnitKind, cell_name, cells_see, get_box_id, get_cell_col, get_cell_id, get_cell_row, get_unit,
+};
+use crate::candidates::Candidates;
+use crate::techniques::{
+    ConjugatePair, Step, Technique, eliminate_seen, find_conjugate_pairs, format_cells,
+};
+
+/// Which kind of units the two strong links and their weak link sit in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkPattern {
+    /// Two parallel lines joined along the crossing line
+    Skyscraper,
+    /// A row and a col joined inside a box
+    TwoStringKite,
+    /// Any other pair of strong links
+    TurbotFish,
+}
+
+impl LinkPattern {
+    fn name(self) -> &'static str {
+        match self {
+            LinkPattern::Skyscraper => "Skyscraper",
+            LinkPattern::TwoStringKite => "Two-String Kite",
+            LinkPattern::TurbotFish => "Turbot Fish",
+        }
+    }
+
+    /// Classify two strong links joined at cells `near_a` and `near_b`
+    fn classify(a: &ConjugatePair, b: &ConjugatePair, near_a: u8, near_b: u8) -> LinkPattern {
+        match (a.unit.kind, b.unit.kind) {
+            (UnitKind::Row, UnitKind::Row) if get_cell_col(near_a) == get_cell_col(near_b) => {
+                LinkPattern::Skyscraper
+            }
+            (UnitKind::Col, UnitKind::Col) if get_cell_row(near_a) == get_cell_row(near_b) => {
+                LinkPattern::Skyscraper
+            }
+            (UnitKind::Row, UnitKind::Col) | (UnitKind::Col, UnitKind::Row)
+                if get_box_id(near_a) == get_box_id(near_b) =>
+            {
+                LinkPattern::TwoStringKite
+            }
+            _ => LinkPattern::TurbotFish,
+        }
+    }
+
+    /// Search every value for a pair of strong links of this pattern
+    fn find(self, pencil_values: &[Candidates]) -> Option<Step> {
+        for value in 1..10u8 {
+            let links = find_conjugate_pairs(pencil_values, value);
+            for (i, a) in links.iter().enumerate() {
+                for b in links[i + 1..].iter() {
+                    // Links sharing a cell do not form a chain
+                    if a.cells.iter().any(|c| b.cells.contains(c)) {
+                        continue;
+                    }
+                    for (near_a, far_a) in [(0, 1), (1, 0)] {
+                        for (near_b, far_b) in [(0, 1), (1, 0)] {
+                            let (near_a, far_a) = (a.cells[near_a], a.cells[far_a]);
+                            let (near_b, far_b) = (b.cells[near_b], b.cells[far_b]);
+                            if !cells_see(near_a, near_b)
+                                || LinkPattern::classify(a, b, near_a, near_b) != self
+                            {
+                                continue;
+                            }
+                            let eliminations =
+                                eliminate_seen(pencil_values, value, &[far_a, far_b]);
+                            if eliminations.is_empty() {
+                                continue;
+                            }
+                            return Some(Step {
+                                technique: self.name(),
+                                description: format!(
+                                    "{} strong links {}={} ({}) and {}={} ({}), one of {} or {} is {}",
+                                    value,
+                                    cell_name(far_a),
+                                    cell_name(near_a),
+                                    a.unit,
+                                    cell_name(near_b),
+                                    cell_name(far_b),
+                                    b.unit,
+                                    cell_name(far_a),
+                                    cell_name(far_b),
+                                    value
+                                ),
+                                eliminations,
+                                placements: Vec::new(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Skyscraper
+/// Two strong links in parallel rows (or cols) with one end of each in the same col (or row)
+pub struct Skyscraper;
+
+impl Technique for Skyscraper {
+    fn name(&self) -> &'static str {
+        LinkPattern::Skyscraper.name()
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        LinkPattern::Skyscraper.find(pencil_values)
+    }
+}
+
+/// Two-String Kite
+/// A strong link in a row and one in a col with one end of each in the same box
+pub struct TwoStringKite;
+
+impl Technique for TwoStringKite {
+    fn name(&self) -> &'static str {
+        LinkPattern::TwoStringKite.name()
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        LinkPattern::TwoStringKite.find(pencil_values)
+    }
+}
+
+/// Turbot Fish
+/// Any other two strong links joined by a weak link, such as those using a box
+pub struct TurbotFish;
+
+impl Technique for TurbotFish {
+    fn name(&self) -> &'static str {
+        LinkPattern::TurbotFish.name()
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        LinkPattern::TurbotFish.find(pencil_values)
+    }
+}
+
+/// Empty Rectangle
+/// A value in a box confined to one row and one col of the box (the rest of the box is empty of it)
+/// works as a bent strong link. Joined to a strong link in a line outside the box, it removes the
+/// value where the far end of that link crosses the other arm of the box
+pub struct EmptyRectangle;
+
+impl Technique for EmptyRectangle {
+    fn name(&self) -> &'static str {
+        "Empty Rectangle"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for value in 1..10u8 {
+            let links = find_conjugate_pairs(pencil_values, value);
+            for box_id in 0..9u8 {
+                let c_box = get_unit(UnitKind::Box, box_id);
+                let cells: Vec<u8> = c_box
+                    .cells
+                    .iter()
+                    .copied()
+                    .filter(|&c| pencil_values[c as usize].contains(value))
+                    .collect();
+                if cells.len() < 2 {
+                    continue;
+                }
+                let box_rows: Vec<u8> = (0..3).map(|r| (box_id / 3) * 3 + r).collect();
+                let box_cols: Vec<u8> = (0..3).map(|c| (box_id % 3) * 3 + c).collect();
+                for &er_row in box_rows.iter() {
+                    for &er_col in box_cols.iter() {
+                        let in_row = |c: &u8| get_cell_row(*c) == er_row;
+                        let in_col = |c: &u8| get_cell_col(*c) == er_col;
+                        // Every mark on the cross, with marks off the crossing on both arms
+                        if !cells.iter().all(|c| in_row(c) || in_col(c))
+                            || !cells.iter().any(|c| in_row(c) && !in_col(c))
+                            || !cells.iter().any(|c| in_col(c) && !in_row(c))
+                        {
+                            continue;
+                        }
+                        for link in links.iter() {
+                            // The strong link must be a line that misses the box
+                            let target = match link.unit.kind {
+                                UnitKind::Row if !box_rows.contains(&link.unit.id) => {
+                                    far_end_target(link, |c| get_cell_col(c) == er_col)
+                                        .map(|far| get_cell_id(er_row, get_cell_col(far)))
+                                }
+                                UnitKind::Col if !box_cols.contains(&link.unit.id) => {
+                                    far_end_target(link, |c| get_cell_row(c) == er_row)
+                                        .map(|far| get_cell_id(get_cell_row(far), er_col))
+                                }
+                                _ => None,
+                            };
+                            let Some(target) = target else {
+                                continue;
+                            };
+                            if get_box_id(target) == box_id
+                                || !pencil_values[target as usize].contains(value)
+                            {
+                                continue;
+                            }
+                            return Some(Step {
+                                technique: self.name(),
+                                description: format!(
+                                    "{} in {} confined to row {} and col {} ({}) with strong link {}={} ({})",
+                                    value,
+                                    c_box,
+                                    er_row + 1,
+                                    er_col + 1,
+                                    format_cells(&cells),
+                                    cell_name(link.cells[0]),
+                                    cell_name(link.cells[1]),
+                                    link.unit
+                                ),
+                                eliminations: vec![(target, value)],
+                                placements: Vec::new(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Far end of a strong link whose other end meets the arm of an empty rectangle
+fn far_end_target(link: &ConjugatePair, on_arm: impl Fn(u8) -> bool) -> Option<u8> {
+    let [first, second] = link.cells;
+    if on_arm(first) && !on_arm(second) {
+        Some(second)
+    } else if on_arm(second) && !on_arm(first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::techniques::testing::{at, marks};
+
+    #[test]
+    fn skyscraper() {
+        // Rows 1 and 5 joined in col 1, r2c6 sees both r1c5 and r5c6
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (0, 4, 1),
+            (4, 0, 1),
+            (4, 5, 1),
+            (1, 5, 1),
+            (8, 5, 1),
+        ]);
+        let step = Skyscraper.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "1 strong links r1c5=r1c1 (row 1) and r5c1=r5c6 (row 5), one of r1c5 or r5c6 is 1"
+        );
+        assert_eq!(step.eliminations, [at(1, 5, 1)]);
+    }
+
+    #[test]
+    fn two_string_kite() {
+        // Row 1 and col 1 joined in box 1, r8c7 sees both r1c7 and r8c1
+        let pencil_values = marks(&[(0, 1, 1), (0, 6, 1), (2, 0, 1), (7, 0, 1), (7, 6, 1)]);
+        let step = TwoStringKite.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Two-String Kite");
+        assert_eq!(step.eliminations, [at(7, 6, 1)]);
+    }
+
+    #[test]
+    fn turbot_fish() {
+        // Box 1 and row 5 joined in col 3, r1c8 sees both r1c1 and r5c8
+        let pencil_values = marks(&[
+            (0, 0, 1),
+            (2, 2, 1),
+            (4, 2, 1),
+            (4, 7, 1),
+            (0, 7, 1),
+            (8, 7, 1),
+        ]);
+        let links = find_conjugate_pairs(&pencil_values, 1);
+        let in_box = links.iter().find(|l| l.unit.kind == UnitKind::Box).unwrap();
+        let in_row = links.iter().find(|l| l.unit.kind == UnitKind::Row).unwrap();
+        let (r3c3, r5c3) = (get_cell_id(2, 2), get_cell_id(4, 2));
+        assert_eq!(
+            LinkPattern::classify(in_box, in_row, r3c3, r5c3),
+            LinkPattern::TurbotFish
+        );
+
+        let step = TurbotFish.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.technique, "Turbot Fish");
+        assert_eq!(step.eliminations, [at(0, 7, 1)]);
+    }
+
+    #[test]
+    fn empty_rectangle() {
+        // Box 1 holds 1 only on row 1 and col 1, joined to the strong link r5c1=r5c6
+        let pencil_values = marks(&[(0, 1, 1), (1, 0, 1), (4, 0, 1), (4, 5, 1), (0, 5, 1)]);
+        let step = EmptyRectangle.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "1 in box 1 confined to row 1 and col 1 (r1c2,r2c1) with strong link r5c1=r5c6 (row 5)"
+        );
+        assert_eq!(step.eliminations, [at(0, 5, 1)]);
+    }
+
+    #[test]
+    fn empty_rectangle_skips_target_in_box() {
+        // The strong link r5c1=r5c3 points back at r1c3, inside the empty rectangle's box
+        let pencil_values = marks(&[(0, 1, 1), (0, 2, 1), (1, 0, 1), (4, 0, 1), (4, 2, 1)]);
+        assert!(EmptyRectangle.find(&[0; 81], &pencil_values).is_none());
+    }
+}
@@ -0,0 +1,195 @@
+//! Naked and hidden subsets (pairs, triples and quads) within a single box|row|col
+
+use crate::board_ctrl::get_all_units;
+use crate::candidates::Candidates;
+use crate::techniques::{Step, Technique, combinations, format_cells};
+
+/// Name of a subset technique for a given size
+fn subset_name(naked: bool, size: usize) -> &'static str {
+    match (naked, size) {
+        (true, 2) => "Naked Pair",
+        (true, 3) => "Naked Triple",
+        (true, 4) => "Naked Quad",
+        (false, 2) => "Hidden Pair",
+        (false, 3) => "Hidden Triple",
+        (false, 4) => "Hidden Quad",
+        (true, _) => "Naked Subset",
+        (false, _) => "Hidden Subset",
+    }
+}
+
+/// Naked subset
+/// `size` cells of a unit that together hold exactly `size` values.
+/// Those values must go in those cells, so they are removed from the rest of the unit
+pub struct NakedSubset {
+    pub size: usize,
+}
+
+impl Technique for NakedSubset {
+    fn name(&self) -> &'static str {
+        subset_name(true, self.size)
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for unit in get_all_units() {
+            let empty: Vec<u8> = unit
+                .cells
+                .iter()
+                .copied()
+                .filter(|&c| !pencil_values[c as usize].is_empty())
+                .collect();
+            // Only cells with no more marks than the subset size can take part
+            let small: Vec<u8> = empty
+                .iter()
+                .copied()
+                .filter(|&c| pencil_values[c as usize].len() <= self.size)
+                .collect();
+            for subset in combinations(&small, self.size) {
+                let mark = subset
+                    .iter()
+                    .fold(Candidates::NONE, |acc, &c| acc | pencil_values[c as usize]);
+                if mark.len() != self.size {
+                    continue;
+                }
+                let eliminations: Vec<(u8, u8)> = empty
+                    .iter()
+                    .filter(|c| !subset.contains(c))
+                    .flat_map(|&c| {
+                        (pencil_values[c as usize] & mark)
+                            .iter()
+                            .map(move |v| (c, v))
+                    })
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: self.name(),
+                        description: format!("{} in {} ({})", mark, format_cells(&subset), unit),
+                        eliminations,
+                        placements: Vec::new(),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Hidden subset
+/// `size` values of a unit that can only go in the same `size` cells.
+/// Those cells must hold those values, so every other mark is removed from them
+pub struct HiddenSubset {
+    pub size: usize,
+}
+
+impl Technique for HiddenSubset {
+    fn name(&self) -> &'static str {
+        subset_name(false, self.size)
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for unit in get_all_units() {
+            // Cells of the unit that each value could go in, for values not yet written in
+            let positions: Vec<(u8, Vec<u8>)> = (1..10u8)
+                .map(|v| {
+                    let cells: Vec<u8> = unit
+                        .cells
+                        .iter()
+                        .copied()
+                        .filter(|&c| pencil_values[c as usize].contains(v))
+                        .collect();
+                    (v, cells)
+                })
+                .filter(|(_, cells)| !cells.is_empty() && cells.len() <= self.size)
+                .collect();
+            for subset in combinations(&(0..positions.len()).collect::<Vec<usize>>(), self.size) {
+                let mut cells: Vec<u8> = subset
+                    .iter()
+                    .flat_map(|&i| positions[i].1.iter().copied())
+                    .collect();
+                cells.sort_unstable();
+                cells.dedup();
+                if cells.len() != self.size {
+                    continue;
+                }
+                let mark = subset.iter().fold(Candidates::NONE, |acc, &i| {
+                    acc | Candidates::only(positions[i].0)
+                });
+                let eliminations: Vec<(u8, u8)> = cells
+                    .iter()
+                    .flat_map(|&c| {
+                        (pencil_values[c as usize] - mark)
+                            .iter()
+                            .map(move |v| (c, v))
+                    })
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: self.name(),
+                        description: format!("{} in {} ({})", mark, format_cells(&cells), unit),
+                        eliminations,
+                        placements: Vec::new(),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::techniques::testing::{at, cells};
+
+    #[test]
+    fn naked_pair() {
+        let pencil_values = cells(&[(0, 0, 12), (0, 1, 12), (0, 2, 123)]);
+        let step = NakedSubset { size: 2 }
+            .find(&[0; 81], &pencil_values)
+            .unwrap();
+        assert_eq!(step.technique, "Naked Pair");
+        assert_eq!(step.description, "{1,2} in r1c1,r1c2 (box 1)");
+        assert_eq!(step.eliminations, [at(0, 2, 1), at(0, 2, 2)]);
+    }
+
+    #[test]
+    fn naked_quad() {
+        // Spread over three boxes of row 1, so only the row holds the quad
+        let pencil_values = cells(&[(0, 0, 12), (0, 3, 23), (0, 4, 15), (0, 6, 34), (0, 8, 14)]);
+        let step = NakedSubset { size: 4 }
+            .find(&[0; 81], &pencil_values)
+            .unwrap();
+        assert_eq!(step.technique, "Naked Quad");
+        assert_eq!(step.description, "{1,2,3,4} in r1c1,r1c4,r1c7,r1c9 (row 1)");
+        assert_eq!(step.eliminations, [at(0, 4, 1)]);
+    }
+
+    #[test]
+    fn hidden_pair() {
+        let pencil_values = cells(&[(0, 0, 125), (1, 1, 126), (0, 1, 56)]);
+        let step = HiddenSubset { size: 2 }
+            .find(&[0; 81], &pencil_values)
+            .unwrap();
+        assert_eq!(step.technique, "Hidden Pair");
+        assert_eq!(step.description, "{1,2} in r1c1,r2c2 (box 1)");
+        assert_eq!(step.eliminations, [at(0, 0, 5), at(1, 1, 6)]);
+    }
+
+    #[test]
+    fn hidden_quad() {
+        let pencil_values = cells(&[
+            (0, 0, 127),
+            (0, 1, 789),
+            (0, 3, 238),
+            (0, 4, 78),
+            (0, 6, 34),
+            (0, 8, 149),
+        ]);
+        let step = HiddenSubset { size: 4 }
+            .find(&[0; 81], &pencil_values)
+            .unwrap();
+        assert_eq!(step.technique, "Hidden Quad");
+        assert_eq!(step.description, "{1,2,3,4} in r1c1,r1c4,r1c7,r1c9 (row 1)");
+        assert_eq!(step.eliminations, [at(0, 0, 7), at(0, 3, 8), at(0, 8, 9)]);
+    }
+}
@@ -0,0 +1,495 @@
+//! Techniques that assume the puzzle has exactly one solution
+//!
+//! A deadly pattern is a rectangle of four cells over two rows, two cols and two boxes holding
+//! only two values `a` and `b`. The values could be swapped without breaking any rule, giving a
+//! second solution, so a proper puzzle can never end in one. These deductions are wrong for
+//! puzzles with several solutions, see `Pipeline::with_uniqueness`.
+
+use crate::board_ctrl::{
+    Unit, UnitKind, cell_name, get_all_units, get_box_id, get_cell_col, get_cell_id, get_cell_row,
+    get_unit,
+};
+use crate::candidates::Candidates;
+use crate::techniques::{Step, Technique, combinations, eliminate_seen, format_cells};
+
+/// Every rectangle over two rows, two cols and exactly two boxes
+/// Cells are `[r1c1, r1c2, r2c1, r2c2]`, so diagonals are `(0, 3)` and `(1, 2)`
+fn rectangles() -> Vec<[u8; 4]> {
+    let mut found = Vec::new();
+    for r1 in 0..9 {
+        for r2 in r1 + 1..9 {
+            for c1 in 0..9 {
+                for c2 in c1 + 1..9 {
+                    let cells = [
+                        get_cell_id(r1, c1),
+                        get_cell_id(r1, c2),
+                        get_cell_id(r2, c1),
+                        get_cell_id(r2, c2),
+                    ];
+                    let mut boxes: Vec<u8> = cells.iter().map(|&c| get_box_id(c)).collect();
+                    boxes.sort_unstable();
+                    boxes.dedup();
+                    if boxes.len() == 2 {
+                        found.push(cells);
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Rows and cols shared by both cells, plus the box if they share it
+fn shared_units(a: u8, b: u8) -> Vec<&'static Unit> {
+    let mut units = Vec::new();
+    if get_cell_row(a) == get_cell_row(b) {
+        units.push(get_unit(UnitKind::Row, get_cell_row(a)));
+    }
+    if get_cell_col(a) == get_cell_col(b) {
+        units.push(get_unit(UnitKind::Col, get_cell_col(a)));
+    }
+    if get_box_id(a) == get_box_id(b) {
+        units.push(get_unit(UnitKind::Box, get_box_id(a)));
+    }
+    units
+}
+
+/// Build a rectangle step
+fn rectangle_step(
+    technique: &'static str,
+    cells: &[u8; 4],
+    pair: Candidates,
+    eliminations: Vec<(u8, u8)>,
+) -> Step {
+    let values: Vec<u8> = pair.iter().collect();
+    Step {
+        technique,
+        description: format!(
+            "{}/{} in {} would be a deadly pattern",
+            values[0],
+            values[1],
+            format_cells(cells)
+        ),
+        eliminations,
+        placements: Vec::new(),
+    }
+}
+
+/// Unique Rectangles types 1 to 6
+/// Four empty cells of a rectangle all marked for `a` and `b`: at least one cell outside the
+/// bivalue {a,b} floor must take another value
+pub struct UniqueRectangle;
+
+impl UniqueRectangle {
+    /// Check one rectangle for one pair of values, trying the types in order
+    fn check(pencil_values: &[Candidates], cells: &[u8; 4], pair: Candidates) -> Option<Step> {
+        let roof: Vec<u8> = cells
+            .iter()
+            .copied()
+            .filter(|&c| pencil_values[c as usize] != pair)
+            .collect();
+        let step = |technique, eliminations: Vec<(u8, u8)>| {
+            if eliminations.is_empty() {
+                None
+            } else {
+                Some(rectangle_step(technique, cells, pair, eliminations))
+            }
+        };
+        let extra = roof
+            .iter()
+            .fold(Candidates::NONE, |acc, &c| acc | pencil_values[c as usize])
+            - pair;
+
+        // Type 1: the only cell with extra values can not be `a` or `b`
+        if roof.len() == 1 {
+            let cell = roof[0];
+            let eliminations = pair.iter().map(|v| (cell, v)).collect();
+            return step("Unique Rectangle Type 1", eliminations);
+        }
+
+        // Types 2 and 5: every roof cell has the same single extra value, one of them holds it
+        if extra.len() == 1
+            && roof
+                .iter()
+                .all(|&c| pencil_values[c as usize] - pair == extra)
+            && let Some(value) = extra.single()
+        {
+            let in_line = roof.len() == 2
+                && (get_cell_row(roof[0]) == get_cell_row(roof[1])
+                    || get_cell_col(roof[0]) == get_cell_col(roof[1]));
+            let technique = if in_line {
+                "Unique Rectangle Type 2"
+            } else {
+                "Unique Rectangle Type 5"
+            };
+            let found = step(technique, eliminate_seen(pencil_values, value, &roof));
+            if found.is_some() {
+                return found;
+            }
+        }
+        if roof.len() != 2 {
+            return None;
+        }
+        let (r1, r2) = (roof[0], roof[1]);
+        let in_line = get_cell_row(r1) == get_cell_row(r2) || get_cell_col(r1) == get_cell_col(r2);
+
+        if in_line {
+            // Type 3: the extra values act as one more cell in a naked subset of a shared unit
+            for unit in shared_units(r1, r2) {
+                let others: Vec<u8> = unit
+                    .cells
+                    .iter()
+                    .copied()
+                    .filter(|&c| c != r1 && c != r2 && !pencil_values[c as usize].is_empty())
+                    .collect();
+                for size in 1..=3 {
+                    for subset in combinations(&others, size) {
+                        let mark = subset
+                            .iter()
+                            .fold(extra, |acc, &c| acc | pencil_values[c as usize]);
+                        if mark.len() != size + 1 {
+                            continue;
+                        }
+                        let eliminations: Vec<(u8, u8)> = others
+                            .iter()
+                            .filter(|c| !subset.contains(c))
+                            .flat_map(|&c| {
+                                (pencil_values[c as usize] & mark)
+                                    .iter()
+                                    .map(move |v| (c, v))
+                            })
+                            .collect();
+                        let found = step("Unique Rectangle Type 3", eliminations);
+                        if found.is_some() {
+                            return found;
+                        }
+                    }
+                }
+            }
+
+            // Type 4: one of the pair is locked to the roof in a shared unit, so the other is not there
+            for unit in shared_units(r1, r2) {
+                for value in pair {
+                    let locked = unit
+                        .cells
+                        .iter()
+                        .all(|&c| c == r1 || c == r2 || !pencil_values[c as usize].contains(value));
+                    if !locked {
+                        continue;
+                    }
+                    let Some(other) = (pair - Candidates::only(value)).single() else {
+                        continue;
+                    };
+                    let found = step("Unique Rectangle Type 4", vec![(r1, other), (r2, other)]);
+                    if found.is_some() {
+                        return found;
+                    }
+                }
+            }
+        } else {
+            // Type 6: an X-Wing of one value on the rectangle keeps it out of the roof diagonal
+            for value in pair {
+                let rows = [get_cell_row(cells[0]), get_cell_row(cells[3])];
+                let cols = [get_cell_col(cells[0]), get_cell_col(cells[3])];
+                let lines_locked = |kind: UnitKind, ids: [u8; 2]| {
+                    ids.iter().all(|&id| {
+                        get_unit(kind, id).cells.iter().all(|&c| {
+                            cells.contains(&c) || !pencil_values[c as usize].contains(value)
+                        })
+                    })
+                };
+                if lines_locked(UnitKind::Row, rows) || lines_locked(UnitKind::Col, cols) {
+                    let found = step("Unique Rectangle Type 6", vec![(r1, value), (r2, value)]);
+                    if found.is_some() {
+                        return found;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Technique for UniqueRectangle {
+    fn name(&self) -> &'static str {
+        "Unique Rectangle"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for cells in rectangles() {
+            let common = cells.iter().fold(Candidates::all(9), |acc, &c| {
+                acc & pencil_values[c as usize]
+            });
+            if cells.iter().any(|&c| pencil_values[c as usize].is_empty()) || common.len() < 2 {
+                continue;
+            }
+            for pair in combinations(&common.iter().collect::<Vec<u8>>(), 2) {
+                let pair_mark = Candidates::only(pair[0]) | Candidates::only(pair[1]);
+                // At least two cells must hold only the pair to be worth checking
+                let floor = cells
+                    .iter()
+                    .filter(|&&c| pencil_values[c as usize] == pair_mark)
+                    .count();
+                if floor < 2 {
+                    continue;
+                }
+                if let Some(step) = UniqueRectangle::check(pencil_values, &cells, pair_mark) {
+                    return Some(step);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Bivalue Universal Grave + 1
+/// When every empty cell has two pencil marks except one cell with three, the value appearing
+/// three times in that cell's units must go there, or the board would be left with two solutions
+pub struct BugPlusOne;
+
+impl Technique for BugPlusOne {
+    fn name(&self) -> &'static str {
+        "BUG+1"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let mut extra_cell = None;
+        for (i, mark) in pencil_values.iter().enumerate() {
+            match mark.len() {
+                0 | 2 => {}
+                3 if extra_cell.is_none() => extra_cell = Some(i as u8),
+                _ => return None,
+            }
+        }
+        let cell = extra_cell?;
+        let units: Vec<_> = get_all_units()
+            .iter()
+            .filter(|unit| unit.cells.contains(&cell))
+            .collect();
+        let value = pencil_values[cell as usize].iter().find(|&v| {
+            units.iter().all(|unit| {
+                unit.cells
+                    .iter()
+                    .filter(|&&c| pencil_values[c as usize].contains(v))
+                    .count()
+                    == 3
+            })
+        })?;
+        Some(Step {
+            technique: self.name(),
+            description: format!(
+                "every other empty cell has two pencil marks, {} appears three times around {}",
+                value,
+                cell_name(cell)
+            ),
+            eliminations: Vec::new(),
+            placements: vec![(cell, value)],
+        })
+    }
+}
+
+/// Avoidable Rectangles
+/// Like unique rectangles but using cells solved along the way. Clues can not be swapped,
+/// so every solved corner must not be a given
+pub struct AvoidableRectangle {
+    pub givens: Vec<bool>,
+}
+
+impl Technique for AvoidableRectangle {
+    fn name(&self) -> &'static str {
+        "Avoidable Rectangle"
+    }
+
+    fn find(&self, board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let solved = |c: u8| board[c as usize] != 0 && !self.givens[c as usize];
+        for cells in rectangles() {
+            // Corners across each diagonal must hold the same value in a deadly pattern
+            for (corner, opposite, side_a, side_b) in
+                [(0, 3, 1, 2), (3, 0, 1, 2), (1, 2, 0, 3), (2, 1, 0, 3)]
+            {
+                let (corner, opposite) = (cells[corner], cells[opposite]);
+                let (side_a, side_b) = (cells[side_a], cells[side_b]);
+
+                // Type 1: three solved corners, the last can not complete the pattern
+                if !pencil_values[corner as usize].is_empty()
+                    && solved(opposite)
+                    && solved(side_a)
+                    && solved(side_b)
+                    && board[side_a as usize] == board[side_b as usize]
+                    && board[opposite as usize] != board[side_a as usize]
+                {
+                    let value = board[opposite as usize];
+                    if pencil_values[corner as usize].contains(value) {
+                        return Some(rectangle_step(
+                            "Avoidable Rectangle Type 1",
+                            &cells,
+                            Candidates::only(value) | Candidates::only(board[side_a as usize]),
+                            vec![(corner, value)],
+                        ));
+                    }
+                }
+            }
+
+            // Type 2: two solved corners in a line, the other two share one extra value
+            for (solved_pair, open_pair) in [
+                ((0, 1), (2, 3)),
+                ((2, 3), (0, 1)),
+                ((0, 2), (1, 3)),
+                ((1, 3), (0, 2)),
+            ] {
+                let (a, b) = (cells[solved_pair.0], cells[solved_pair.1]);
+                let (c, d) = (cells[open_pair.0], cells[open_pair.1]);
+                if !solved(a) || !solved(b) {
+                    continue;
+                }
+                let (value_a, value_b) = (board[a as usize], board[b as usize]);
+                // `c` is across from `b` so would take `value_b`, `d` would take `value_a`
+                let (mark_c, mark_d) = (pencil_values[c as usize], pencil_values[d as usize]);
+                let (only_a, only_b) = (Candidates::only(value_a), Candidates::only(value_b));
+                if mark_c.len() != 2
+                    || !mark_c.contains(value_b)
+                    || mark_d.len() != 2
+                    || !mark_d.contains(value_a)
+                    || mark_c - only_b != mark_d - only_a
+                {
+                    continue;
+                }
+                let Some(value) = (mark_c - only_b).single() else {
+                    continue;
+                };
+                let eliminations = eliminate_seen(pencil_values, value, &[c, d]);
+                if !eliminations.is_empty() {
+                    return Some(rectangle_step(
+                        "Avoidable Rectangle Type 2",
+                        &cells,
+                        only_a | only_b,
+                        eliminations,
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::techniques::Pipeline;
+    use crate::techniques::testing::{at, board, cells, sorted};
+
+    // Every rectangle below is r1c1, r1c4, r2c1, r2c4, over boxes 1 and 2
+
+    fn unique_rectangle(pencil_values: &[Candidates]) -> Option<Step> {
+        UniqueRectangle.find(&[0; 81], pencil_values)
+    }
+
+    #[test]
+    fn unique_rectangle_type_1() {
+        let pencil_values = cells(&[(0, 0, 12), (0, 3, 12), (1, 0, 12), (1, 3, 125)]);
+        let step = unique_rectangle(&pencil_values).unwrap();
+        assert_eq!(step.technique, "Unique Rectangle Type 1");
+        assert_eq!(step.eliminations, vec![at(1, 3, 1), at(1, 3, 2)]);
+    }
+
+    #[test]
+    fn unique_rectangle_type_2() {
+        // Both roof cells in row 2 have the extra 5, so r2c6 can't be 5
+        let pencil_values = cells(&[(0, 0, 12), (0, 3, 12), (1, 0, 125), (1, 3, 125), (1, 5, 58)]);
+        let step = unique_rectangle(&pencil_values).unwrap();
+        assert_eq!(step.technique, "Unique Rectangle Type 2");
+        assert_eq!(step.eliminations, vec![at(1, 5, 5)]);
+    }
+
+    #[test]
+    fn unique_rectangle_type_3() {
+        // The extra 3 and 4 of the roof pair up with r2c7 {3,4} in row 2
+        let pencil_values = cells(&[
+            (0, 0, 12),
+            (0, 3, 12),
+            (1, 0, 123),
+            (1, 3, 124),
+            (1, 6, 34),
+            (1, 7, 348),
+        ]);
+        let step = unique_rectangle(&pencil_values).unwrap();
+        assert_eq!(step.technique, "Unique Rectangle Type 3");
+        assert_eq!(sorted(step.eliminations), vec![at(1, 7, 3), at(1, 7, 4)]);
+    }
+
+    #[test]
+    fn unique_rectangle_type_4() {
+        // 1 is only in the roof in row 2, so neither roof cell can be 2
+        let pencil_values = cells(&[(0, 0, 12), (0, 3, 12), (1, 0, 125), (1, 3, 126), (1, 7, 27)]);
+        let step = unique_rectangle(&pencil_values).unwrap();
+        assert_eq!(step.technique, "Unique Rectangle Type 4");
+        assert_eq!(sorted(step.eliminations), vec![at(1, 0, 2), at(1, 3, 2)]);
+    }
+
+    #[test]
+    fn unique_rectangle_type_6() {
+        // The roof is a diagonal and 1 is only in the rectangle in rows 1 and 2
+        let pencil_values = cells(&[(0, 0, 12), (0, 3, 125), (1, 0, 126), (1, 3, 12), (4, 0, 17)]);
+        let step = unique_rectangle(&pencil_values).unwrap();
+        assert_eq!(step.technique, "Unique Rectangle Type 6");
+        assert_eq!(sorted(step.eliminations), vec![at(1, 0, 1), at(0, 3, 1)]);
+    }
+
+    #[test]
+    fn unique_rectangle_needs_two_floor_cells() {
+        let pencil_values = cells(&[(0, 0, 12), (0, 3, 123), (1, 0, 124), (1, 3, 125)]);
+        assert_eq!(unique_rectangle(&pencil_values), None);
+    }
+
+    #[test]
+    fn bug_plus_one() {
+        // 1 is in three cells of row 1, col 1 and box 1, all through r1c1
+        let pencil_values = cells(&[
+            (0, 0, 123),
+            (0, 1, 12),
+            (1, 1, 12),
+            (0, 5, 12),
+            (3, 0, 12),
+            (6, 0, 12),
+        ]);
+        let step = BugPlusOne.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(step.placements, vec![at(0, 0, 1)]);
+        assert!(step.eliminations.is_empty());
+        // A second cell with three marks is no longer a BUG
+        let mut pencil_values = pencil_values;
+        pencil_values[at(6, 0, 0).0 as usize].insert(4);
+        assert_eq!(BugPlusOne.find(&[0; 81], &pencil_values), None);
+    }
+
+    #[test]
+    fn avoidable_rectangle_type_1() {
+        // r1c4 and r2c1 were solved as 2, r2c4 as 1, so r1c1 can't be 1
+        let board = board(&[(0, 3, 2), (1, 0, 2), (1, 3, 1)]);
+        let pencil_values = cells(&[(0, 0, 13)]);
+        let technique = AvoidableRectangle {
+            givens: vec![false; 81],
+        };
+        let step = technique.find(&board, &pencil_values).unwrap();
+        assert_eq!(step.technique, "Avoidable Rectangle Type 1");
+        assert_eq!(step.eliminations, vec![at(0, 0, 1)]);
+    }
+
+    #[test]
+    fn avoidable_rectangle_skips_givens() {
+        let board = board(&[(0, 3, 2), (1, 0, 2), (1, 3, 1)]);
+        let pencil_values = cells(&[(0, 0, 13)]);
+        let technique = AvoidableRectangle {
+            givens: board.iter().map(|&v| v != 0).collect(),
+        };
+        assert_eq!(technique.find(&board, &pencil_values), None);
+    }
+
+    #[test]
+    fn uniqueness_is_opt_in() {
+        let uniqueness = ["Unique Rectangle", "BUG+1", "Avoidable Rectangle"];
+        let names = Pipeline::default().names();
+        assert!(uniqueness.iter().all(|name| !names.contains(name)));
+        let names = Pipeline::with_uniqueness(&[false; 81]).names();
+        assert!(uniqueness.iter().all(|name| names.contains(name)));
+    }
+}
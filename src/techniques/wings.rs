@@ -0,0 +1,240 @@
+//! Wings built from cells with two or three pencil marks (bivalue and trivalue cells)
+//!
+//! A pivot cell sees two pincer cells. Whatever value the pivot takes, one of the pincers is forced
+//! to a shared value `z`, so `z` is removed from every cell seeing the pincers.
+
+use crate::board_ctrl::{cell_name, cells_see, get_peer_cells};
+use crate::candidates::Candidates;
+use crate::techniques::{Step, Technique, eliminate_seen, find_conjugate_pairs};
+
+/// Build a wing step naming the pivot and pincers
+fn wing_step(
+    technique: &'static str,
+    pencil_values: &[Candidates],
+    pivot: u8,
+    pincers: [u8; 2],
+    value: u8,
+    eliminations: Vec<(u8, u8)>,
+) -> Step {
+    Step {
+        technique,
+        description: format!(
+            "pivot {}{} with pincers {}{} and {}{} removes {}",
+            cell_name(pivot),
+            pencil_values[pivot as usize],
+            cell_name(pincers[0]),
+            pencil_values[pincers[0] as usize],
+            cell_name(pincers[1]),
+            pencil_values[pincers[1] as usize],
+            value
+        ),
+        eliminations,
+        placements: Vec::new(),
+    }
+}
+
+/// Peers of a cell with exactly two pencil marks
+fn bivalue_peers(pencil_values: &[Candidates], cell_id: u8) -> Vec<u8> {
+    get_peer_cells(cell_id)
+        .iter()
+        .copied()
+        .filter(|&c| pencil_values[c as usize].len() == 2)
+        .collect()
+}
+
+/// XY-Wing
+/// A pivot {x,y} sees pincers {x,z} and {y,z}, so one pincer is `z`
+pub struct XYWing;
+
+impl Technique for XYWing {
+    fn name(&self) -> &'static str {
+        "XY-Wing"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for pivot in 0..81u8 {
+            let pivot_mark = pencil_values[pivot as usize];
+            if pivot_mark.len() != 2 {
+                continue;
+            }
+            let pincers = bivalue_peers(pencil_values, pivot);
+            for (i, &a) in pincers.iter().enumerate() {
+                for &b in pincers[i + 1..].iter() {
+                    let (mark_a, mark_b) = (pencil_values[a as usize], pencil_values[b as usize]);
+                    if (mark_a & pivot_mark).len() != 1
+                        || (mark_b & pivot_mark).len() != 1
+                        || !(mark_a & mark_b & pivot_mark).is_empty()
+                        || ((mark_a & mark_b) - pivot_mark).len() != 1
+                    {
+                        continue;
+                    }
+                    let Some(z) = (mark_a & mark_b).single() else {
+                        continue;
+                    };
+                    let eliminations = eliminate_seen(pencil_values, z, &[a, b]);
+                    if !eliminations.is_empty() {
+                        return Some(wing_step(
+                            self.name(),
+                            pencil_values,
+                            pivot,
+                            [a, b],
+                            z,
+                            eliminations,
+                        ));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// XYZ-Wing
+/// A pivot {x,y,z} sees pincers {x,z} and {y,z}, so the pivot or a pincer is `z`
+pub struct XYZWing;
+
+impl Technique for XYZWing {
+    fn name(&self) -> &'static str {
+        "XYZ-Wing"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        for pivot in 0..81u8 {
+            let pivot_mark = pencil_values[pivot as usize];
+            if pivot_mark.len() != 3 {
+                continue;
+            }
+            let pincers: Vec<u8> = bivalue_peers(pencil_values, pivot)
+                .into_iter()
+                .filter(|&c| (pencil_values[c as usize] - pivot_mark).is_empty())
+                .collect();
+            for (i, &a) in pincers.iter().enumerate() {
+                for &b in pincers[i + 1..].iter() {
+                    let (mark_a, mark_b) = (pencil_values[a as usize], pencil_values[b as usize]);
+                    if mark_a == mark_b {
+                        continue;
+                    }
+                    let Some(z) = (mark_a & mark_b).single() else {
+                        continue;
+                    };
+                    let eliminations = eliminate_seen(pencil_values, z, &[pivot, a, b]);
+                    if !eliminations.is_empty() {
+                        return Some(wing_step(
+                            self.name(),
+                            pencil_values,
+                            pivot,
+                            [a, b],
+                            z,
+                            eliminations,
+                        ));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// W-Wing
+/// Two cells {x,y} that do not see each other, joined by a strong link on `x`:
+/// a unit where `x` can only go in two cells, one seeing each {x,y} cell.
+/// One of the {x,y} cells must be `y`
+pub struct WWing;
+
+impl Technique for WWing {
+    fn name(&self) -> &'static str {
+        "W-Wing"
+    }
+
+    fn find(&self, _board: &[u8], pencil_values: &[Candidates]) -> Option<Step> {
+        let bivalue: Vec<u8> = (0..81u8)
+            .filter(|&c| pencil_values[c as usize].len() == 2)
+            .collect();
+        for (i, &a) in bivalue.iter().enumerate() {
+            for &b in bivalue[i + 1..].iter() {
+                let mark = pencil_values[a as usize];
+                if pencil_values[b as usize] != mark || cells_see(a, b) {
+                    continue;
+                }
+                for x in mark {
+                    let Some(y) = (mark - Candidates::only(x)).single() else {
+                        continue;
+                    };
+                    let eliminations = eliminate_seen(pencil_values, y, &[a, b]);
+                    if eliminations.is_empty() {
+                        continue;
+                    }
+                    for link in find_conjugate_pairs(pencil_values, x) {
+                        let [s1, s2] = link.cells;
+                        if link.cells.contains(&a) || link.cells.contains(&b) {
+                            continue;
+                        }
+                        let joined = (cells_see(s1, a) && cells_see(s2, b))
+                            || (cells_see(s1, b) && cells_see(s2, a));
+                        if joined {
+                            return Some(Step {
+                                technique: self.name(),
+                                description: format!(
+                                    "{} and {} {} joined by strong link on {} in {} ({}={}) removes {}",
+                                    cell_name(a),
+                                    cell_name(b),
+                                    mark,
+                                    x,
+                                    link.unit,
+                                    cell_name(s1),
+                                    cell_name(s2),
+                                    y
+                                ),
+                                eliminations,
+                                placements: Vec::new(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::techniques::testing::{at, cells};
+
+    #[test]
+    fn xy_wing() {
+        // Pivot r1c1 sees r1c5 in its row and r5c1 in its col, r5c5 sees both pincers
+        let pencil_values = cells(&[(0, 0, 12), (0, 4, 13), (4, 0, 23), (4, 4, 39)]);
+        let step = XYWing.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "pivot r1c1{1,2} with pincers r1c5{1,3} and r5c1{2,3} removes 3"
+        );
+        assert_eq!(step.eliminations, [at(4, 4, 3)]);
+    }
+
+    #[test]
+    fn xyz_wing() {
+        // r1c2 sees the pivot r1c1 and both pincers r1c5 and r2c2
+        let pencil_values = cells(&[(0, 0, 123), (0, 4, 13), (1, 1, 23), (0, 1, 39)]);
+        let step = XYZWing.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "pivot r1c1{1,2,3} with pincers r2c2{2,3} and r1c5{1,3} removes 3"
+        );
+        assert_eq!(step.eliminations, [at(0, 1, 3)]);
+    }
+
+    #[test]
+    fn w_wing() {
+        // r1c1 and r5c5 joined by the strong link on 1 in row 9, r1c5 sees both
+        let pencil_values = cells(&[(0, 0, 12), (4, 4, 12), (8, 0, 15), (8, 4, 16), (0, 4, 27)]);
+        let step = WWing.find(&[0; 81], &pencil_values).unwrap();
+        assert_eq!(
+            step.description,
+            "r1c1 and r5c5 {1,2} joined by strong link on 1 in row 9 (r9c1=r9c5) removes 2"
+        );
+        assert_eq!(step.eliminations, [at(0, 4, 2)]);
+    }
+}
@@ -1,83 +1,67 @@
-use crate::board_ctrl::{get_board_box, get_board_col, get_board_row};
-use std::collections::HashSet;
+use crate::board_ctrl::{Size, UnitKind};
 
-/// Validate the provided box for rules of sudoku.
-/// All numbers appear once between 1-9
-///
-/// Parameters:
-/// - `board: &[u8]` - A reference to the board vector
-/// - `box_id: u8` - The box ID from the board
-pub fn box_validation(board: &[u8], box_id: u8) -> bool {
-    let box_to_check = get_board_box(board, box_id);
-    let mut tracker: HashSet<u8> = HashSet::new();
-    for cell in box_to_check.iter() {
-        if *cell == 0 {
+/// Check no value repeats among a unit's cells, 0 (empty) may repeat
+/// Values larger than the unit has cells are never valid
+fn unit_validation(board: &[u8], cells: &[usize]) -> bool {
+    let mut tracker: u32 = 0; // Bit flag of values seen so far
+    for &cell_id in cells {
+        let cell = board[cell_id];
+        if cell == 0 {
             continue;
-        } else if !tracker.insert(*cell) {
+        } else if cell as usize > cells.len() || tracker & (1 << cell) != 0 {
             return false;
         }
+        tracker |= 1 << cell;
     }
     true
 }
 
+/// Validate the provided box for rules of sudoku.
+/// All numbers appear once between 1 and the side of the board
+///
+/// Parameters:
+/// - `board: &[u8]` - A reference to the board vector
+/// - `size: Size` - size of the board
+/// - `box_id: usize` - The box ID from the board
+pub fn box_validation(board: &[u8], size: Size, box_id: usize) -> bool {
+    unit_validation(board, size.geometry().unit(UnitKind::Box, box_id))
+}
+
 /// Validate the provided row for rules of sudoku.
-/// All numbers appear once between 1-9
+/// All numbers appear once between 1 and the side of the board
 ///
 /// Parameters:
 /// - `board: &[u8]` - A reference to the board vector
-/// - `row_id: u8` - The row ID requested (Y axis coordinate)
-pub fn row_validation(board: &[u8], row_id: u8) -> bool {
-    let row_to_check = get_board_row(board, row_id);
-    let mut tracker: HashSet<u8> = HashSet::new();
-    for cell in row_to_check.iter() {
-        if *cell == 0 {
-            continue;
-        } else if !tracker.insert(*cell) {
-            return false;
-        }
-    }
-    true
+/// - `size: Size` - size of the board
+/// - `row_id: usize` - The row ID requested (Y axis coordinate)
+pub fn row_validation(board: &[u8], size: Size, row_id: usize) -> bool {
+    unit_validation(board, size.geometry().unit(UnitKind::Row, row_id))
 }
 
 /// Validate the provided col for rules of sudoku.
-/// All numbers appear once between 1-9
+/// All numbers appear once between 1 and the side of the board
 ///
 /// Parameters:
 /// - `board: &[u8]` - A reference to the board vector
-/// - `col_id: u8` - The col ID requested (X axis coordinate)
-pub fn col_validation(board: &[u8], col_id: u8) -> bool {
-    let col_to_check = get_board_col(board, col_id);
-    let mut tracker: HashSet<u8> = HashSet::new();
-    for cell in col_to_check.iter() {
-        if *cell == 0 {
-            continue;
-        } else if !tracker.insert(*cell) {
-            return false;
-        }
-    }
-    true
+/// - `size: Size` - size of the board
+/// - `col_id: usize` - The col ID requested (X axis coordinate)
+pub fn col_validation(board: &[u8], size: Size, col_id: usize) -> bool {
+    unit_validation(board, size.geometry().unit(UnitKind::Col, col_id))
 }
 
 /// Validate the whole board by checking every box|row|col
+/// A board with the wrong number of cells for its size is never valid
 ///
 /// parameters:
 /// - `board: &[u8]` - a reference to the board vector
+/// - `size: Size` - size of the board
+///
 /// TODO add more verbose failure messages
-pub fn board_validation(board: &[u8]) -> bool {
-    for b in 0..9 {
-        if !box_validation(board, b) {
-            return false;
-        }
-    }
-    for r in 0..9 {
-        if !row_validation(board, r) {
-            return false;
-        }
-    }
-    for c in 0..9 {
-        if !col_validation(board, c) {
-            return false;
-        }
-    }
-    true
+pub fn board_validation(board: &[u8], size: Size) -> bool {
+    board.len() == size.cells()
+        && size
+            .geometry()
+            .units()
+            .iter()
+            .all(|unit| unit_validation(board, unit))
 }